@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::IntErrorKind;
 
 use crate::error::{Error, ErrorKind};
 use crate::parser::syntax::{ParseResult, ParseTree, SyntaxKind};
 use crate::tree::{Leaf, LeafId, Node, NodeId, TreeElement};
+use crate::Span;
 
 use super::value::{
     AssignLHS, LeafData, LeafKind, MaybeTyped, NodeData, NodeKind, Value, ValueData, ValueType,
@@ -21,11 +22,49 @@ pub struct Analyzer<'a> {
     source: &'a str,
     /// List of errors produced
     errors: Vec<Error<'a>>,
+    /// List of warnings produced
+    warnings: Vec<Error<'a>>,
     /// Contains all variables mapped to memory, arranged in nexting level of scopes
     /// Vec<usize> contains all the values defined to this variable, usize is the current value
     lookup: Vec<HashMap<&'a str, (Vec<usize>, usize)>>,
-    /// Current scope which is getting analyzed
-    current_scope: usize,
+    /// Lookup maps of the enclosing scopes, innermost last
+    scopes: Vec<usize>,
+    /// Lookup map of the scope enclosing each one
+    parents: Vec<usize>,
+    /// Labels of the loops enclosing the current node
+    loops: Vec<Option<&'a str>>,
+    /// Number of functions enclosing the current node
+    fx_depth: usize,
+    /// Type of the first value returned by each of the enclosing functions, innermost last
+    returned: Vec<Option<ValueType>>,
+    /// Start of the memory of the `fx` functions enclosing the current node, they can't use the
+    /// variables defined before it
+    frames: Vec<usize>,
+    /// Start of the memory of the lambdas enclosing the current node and the variables they capture
+    captures: Vec<(usize, Vec<Span>)>,
+    /// Memory locations of the functions declared before their definition
+    declared: HashMap<NodeId, usize>,
+    /// Names of the parameters of the functions that can be given by name, by memory location
+    params: HashMap<usize, Vec<&'a str>>,
+    /// Calls whose arguments given by name were reported as not matching the parameters
+    misnamed: HashSet<NodeId>,
+    /// Memory locations of the constants, their value is inlined where they are used
+    constants: HashSet<usize>,
+    /// Memory locations of the variables declared without `mut`, with the span of their name
+    immutable: HashMap<usize, Span>,
+    /// Memory locations of the variables declared by a `let`, with the span of their name
+    bindings: Vec<(usize, Span)>,
+    /// Memory locations of the variables that are used
+    accessed: HashSet<usize>,
+    /// Memory locations of the variables whose type is being inferred, each one depending on the
+    /// one after it
+    inferring: Vec<usize>,
+    /// Structs and enums defined by the program
+    types: HashMap<&'a str, ValueType>,
+    /// Names of the types each type alias of the program refers to
+    aliases: HashMap<&'a str, Vec<&'a str>>,
+    /// Type aliases found referring to themselves, they were reported once for their cycle
+    cyclic: HashSet<&'a str>,
     /// Data
     memory: Vec<Value>,
     tree: Option<ParseTree>,
@@ -38,8 +77,26 @@ impl<'a> Analyzer<'a> {
         Analyzer {
             source,
             errors: result.errors,
+            warnings: Vec::new(),
             lookup: vec![HashMap::new()],
-            current_scope: 0,
+            scopes: vec![0],
+            parents: vec![0],
+            loops: Vec::new(),
+            fx_depth: 0,
+            returned: Vec::new(),
+            frames: Vec::new(),
+            captures: Vec::new(),
+            declared: HashMap::new(),
+            params: HashMap::new(),
+            misnamed: HashSet::new(),
+            constants: HashSet::new(),
+            immutable: HashMap::new(),
+            bindings: Vec::new(),
+            accessed: HashSet::new(),
+            inferring: Vec::new(),
+            types: HashMap::new(),
+            aliases: HashMap::new(),
+            cyclic: HashSet::new(),
             memory: Vec::new(),
             tree: Some(result.parse),
             builder: AnalyzedTreeBuilder::new(),
@@ -53,16 +110,30 @@ impl<'a> Analyzer<'a> {
 
     #[inline]
     fn get_loc(&self, ident: &'a str) -> Option<usize> {
-        self.lookup[..=self.current_scope]
-            .iter()
-            .rev()
+        self.visible()
             .find_map(|map| map.get(ident).and_then(|(v, _)| v.last()))
             .copied()
     }
 
+    /// Lookup maps of the enclosing scopes, innermost first
+    fn visible(&self) -> impl Iterator<Item = &HashMap<&'a str, (Vec<usize>, usize)>> {
+        self.scopes.iter().rev().map(|&scope| &self.lookup[scope])
+    }
+
+    /// Enters a new scope with its own lookup map
+    fn enter_scope(&mut self) {
+        self.parents.push(*self.scopes.last().unwrap());
+        self.scopes.push(self.lookup.len());
+        self.lookup.push(HashMap::new());
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
     #[inline]
     fn insert(&mut self, ident: &'a str, value: Value) {
-        self.lookup[self.current_scope]
+        self.lookup[*self.scopes.last().unwrap()]
             .entry(ident)
             .or_insert((Vec::new(), 0))
             .0
@@ -72,7 +143,11 @@ impl<'a> Analyzer<'a> {
 
     fn resolve_types(&mut self) {
         for i in 0..self.lookup.len() {
-            self.current_scope = i;
+            // the names visible from the scope
+            self.scopes = vec![i];
+            while self.scopes[0] != 0 {
+                self.scopes.insert(0, self.parents[self.scopes[0]]);
+            }
             for (locs, _) in self.lookup[i].clone().values() {
                 for (i, &loc) in locs.iter().enumerate() {
                     self.resolve_type(loc, i);
@@ -89,9 +164,29 @@ impl<'a> Analyzer<'a> {
                 else {
                     unreachable!()
                 };
-                self.memory[loc].type_ = MaybeTyped::Typed(self.compute_type(id, i));
+                self.inferring.push(loc);
+                let type_ = self.compute_type(id, i);
+                self.inferring.pop();
+                self.memory[loc].type_ = MaybeTyped::Typed(type_);
+            }
+            MaybeTyped::InProgress => {
+                let start = self.inferring.iter().position(|&l| l == loc).unwrap();
+                let span = |loc: usize| -> Span {
+                    self.memory[loc]
+                        .syntax
+                        .get_from_builder(&self.builder)
+                        .span()
+                };
+                self.errors
+                    .push(Error::error(ErrorKind::CyclicType, span(loc), self.source));
+                let through = self.inferring[start + 1..]
+                    .iter()
+                    .map(|&l| Error::help(ErrorKind::CyclicDependency, span(l), self.source))
+                    .collect::<Vec<_>>();
+                self.errors.extend(through);
+                // the variables of the cycle end up poisoned
+                self.memory[loc].type_ = MaybeTyped::Typed(ValueType::Poisoned);
             }
-            MaybeTyped::InProgress => panic!("Loopoz"),
             MaybeTyped::Typed(_) => (),
         }
     }
@@ -101,17 +196,10 @@ impl<'a> Analyzer<'a> {
         match ast.kind() {
             Identifier => {
                 let ident = &self.source[ast.span()];
-                match {
-                    self.lookup[..=self.current_scope]
-                        .iter()
-                        .rev()
-                        .find_map(|map| map.get(ident).and_then(|(v, _)| v.last()))
-                } {
-                    Some(&t) => {
+                match self.get_loc(ident) {
+                    Some(t) => {
                         self.resolve_type(t, i);
-                        self.lookup[..=self.current_scope]
-                            .iter()
-                            .rev()
+                        self.visible()
                             .find_map(|map| map.get(ident).and_then(|(v, _)| v.get(i)))
                             .map(|&i| &self.memory[i])
                             .unwrap()
@@ -124,13 +212,85 @@ impl<'a> Analyzer<'a> {
                 }
             }
             Value => {
-                let &a = ast.into_node().unwrap().children().first().unwrap();
-                self.compute_type(TreeElement::Node(a), i)
+                let a = ast
+                    .into_node()
+                    .unwrap()
+                    .children_with_leaves_builder(&self.builder)
+                    .next()
+                    .unwrap();
+                self.compute_type(a, i)
             }
             Pointer => {
                 let &a = ast.into_node().unwrap().children().first().unwrap();
                 ValueType::Pointer(Box::new(self.compute_type(TreeElement::Node(a), i)))
             }
+            List => {
+                let children = ast.into_node().unwrap().children();
+                let (first, len) = (children.first().copied(), children.len());
+                let type_ = match first {
+                    Some(a) => self.compute_type(TreeElement::Node(a), i),
+                    None => ValueType::None,
+                };
+                ValueType::Array(Box::new(type_), len)
+            }
+            Tuple => {
+                let children = ast.into_node().unwrap().children().to_vec();
+                ValueType::Tuple(
+                    children
+                        .into_iter()
+                        .map(|a| self.compute_type(TreeElement::Node(a), i))
+                        .collect(),
+                )
+            }
+            StructLit | EnumLit => ast.type_().type_().unwrap().clone(),
+            Lambda => {
+                let children = ast.into_node().unwrap().children().to_vec();
+                // the last child holds the captures
+                let (&body, params) = children[..children.len() - 1].split_last().unwrap();
+                let mut types = params
+                    .iter()
+                    .map(|&param| match self.builder.node(param).children().first() {
+                        Some(&kind) => TreeElement::Node(kind)
+                            .get_from_builder(&self.builder)
+                            .type_()
+                            .type_()
+                            .cloned()
+                            .unwrap_or(ValueType::Poisoned),
+                        None => ValueType::Number,
+                    })
+                    .collect::<Vec<_>>();
+                types.push(self.compute_type(TreeElement::Node(body), i));
+                ValueType::Closure(types)
+            }
+            Match => {
+                let arms = ast.into_node().unwrap().children();
+                match arms.get(1) {
+                    Some(&arm) => {
+                        let &value = self.builder.node(arm).children().last().unwrap();
+                        self.compute_type(TreeElement::Node(value), i)
+                    }
+                    None => ValueType::None,
+                }
+            }
+            Access => {
+                let mut iter = ast
+                    .into_node()
+                    .unwrap()
+                    .children_with_leaves_builder(&self.builder);
+                let a = iter.next().unwrap();
+                let field = iter
+                    .next()
+                    .map(|f| &self.source[f.get_from_builder(&self.builder).span()]);
+                let a = self.compute_type(a, i);
+                field
+                    .and_then(|field| a.field(field))
+                    .unwrap_or(ValueType::Poisoned)
+            }
+            Index => {
+                let &a = ast.into_node().unwrap().children().first().unwrap();
+                let a = self.compute_type(TreeElement::Node(a), i);
+                a.index().unwrap_or(ValueType::Poisoned)
+            }
             UnaryOp => {
                 let mut iter = ast
                     .into_node()
@@ -155,30 +315,594 @@ impl<'a> Analyzer<'a> {
                 let b = self.compute_type(b, i);
                 a.operate_binary(op, &b).unwrap_or(ValueType::Poisoned)
             }
-            _ => unreachable!(),
+            Call | MethodCall => {
+                // the receiver of a method call precedes it
+                let f = ast
+                    .into_node()
+                    .unwrap()
+                    .children_with_leaves_builder(&self.builder)
+                    .nth((ast.kind() == MethodCall) as usize)
+                    .unwrap();
+                match self.compute_type(f, i) {
+                    ValueType::FnPtr(types) | ValueType::Closure(types) => {
+                        types.last().cloned().unwrap()
+                    }
+                    _ => ValueType::Poisoned,
+                }
+            }
+            // the other values are typed when analyzed, an error was reported for them
+            _ => ValueType::Poisoned,
+        }
+    }
+
+    /// Type of the value an analyzed function body ends with, unknown when the body ends with a
+    /// return or its type isn't inferred yet
+    fn result_type(&self, element: TreeElement<NodeId, LeafId>) -> Option<ValueType> {
+        let TreeElement::Node(id) = element else {
+            return match self
+                .builder
+                .leaf(element.into_leaf().unwrap())
+                .data()
+                .as_deref()
+            {
+                Some(LeafKind::Value(value)) => value.type_.type_().cloned(),
+                _ => Some(ValueType::None),
+            };
+        };
+        let node = self.builder.node(id);
+        match node.kind() {
+            Statement => match node.children_with_leaves_builder(&self.builder).next() {
+                Some(child) => self.result_type(child),
+                None => Some(ValueType::None),
+            },
+            Scope => match node.children().as_slice() {
+                [.., last, _] if self.statement_kind(*last) == ReturnKw => None,
+                [.., last] if self.statement_kind(*last) != SemiColon => {
+                    self.result_type(TreeElement::Node(*last))
+                }
+                _ => Some(ValueType::None),
+            },
+            // the type checker makes sure the other branch agrees
+            Ternary | If if node.children().len() == 3 => {
+                self.result_type(TreeElement::Node(node.children()[1]))
+            }
+            ReturnKw => None,
+            _ => match node.data().as_ref() {
+                Some(data) => data.type_().type_().cloned(),
+                None => Some(ValueType::None),
+            },
+        }
+    }
+
+    /// Kind of the syntax an analyzed statement is made of
+    fn statement_kind(&self, id: NodeId) -> SyntaxKind {
+        let mut element = TreeElement::Node(id);
+        while let TreeElement::Node(id) = element {
+            let node = self.builder.node(id);
+            match node.kind() {
+                Statement | Value => {
+                    match node.children_with_leaves_builder(&self.builder).next() {
+                        Some(child) => element = child,
+                        None => return node.kind(),
+                    }
+                }
+                kind => return kind,
+            }
+        }
+        element.get_from_builder(&self.builder).kind()
+    }
+
+    /// Name of the variable an analyzed index or field access is taken from, if it is taken from
+    /// one and not from the result of a call or a dereference
+    fn place_root(&self, id: NodeId) -> Option<&'a str> {
+        let mut element = TreeElement::Node(id);
+        loop {
+            match element {
+                TreeElement::Node(id) => {
+                    let node = self.builder.node(id);
+                    match node.kind() {
+                        Index | Access | Value => {
+                            element = node.children_with_leaves_builder(&self.builder).next()?
+                        }
+                        _ => return None,
+                    }
+                }
+                TreeElement::Leaf(id) => {
+                    let leaf = self.builder.leaf(id);
+                    return (leaf.kind() == Identifier).then(|| &self.source[leaf.span()]);
+                }
+            }
+        }
+    }
+
+    /// Name and value of an analyzed field node
+    fn field(&self, id: NodeId) -> Option<(Span, TreeElement<NodeId, LeafId>)> {
+        let field = self.builder.node(id);
+        if field.kind() != Field {
+            return None;
+        }
+        let mut iter = field.children_with_leaves_builder(&self.builder);
+        let name = self.builder.leaf(iter.next()?.into_leaf()?);
+        let value = iter.next()?;
+        (name.kind() == Identifier).then(|| (name.span(), value))
+    }
+
+    /// Label attached to a loop, break or continue
+    fn label(&self, tree: &ParseTree, node: &Node<()>) -> Option<(&'a str, Span)> {
+        let label = tree.node(*node.children().first()?);
+        if label.kind() != Label {
+            return None;
         }
+        let ident = label.leaves(tree).iter().find(|l| l.kind() == Identifier)?;
+        Some((&self.source[ident.span()], ident.span()))
     }
 
     pub fn analyze(mut self) -> AnalysisResult<'a> {
         let tree = &self.tree.take().unwrap();
         let root = tree.node(ParseTree::ROOT);
         self.builder.start_node(root.kind(), root.span().start);
+        self.declare_aliases(tree, root);
+        self.declare_functions(tree, root.children());
         for &child in root.children() {
             self.analyze_node(tree, tree.node(child));
         }
         self.builder.finish_node(root.span().end, |_| None);
         self.resolve_types();
 
+        for (loc, span) in std::mem::take(&mut self.bindings) {
+            let name = &self.source[span.clone()];
+            // names starting with an underscore are left unused on purpose
+            if !self.accessed.contains(&loc) && !name.starts_with('_') {
+                self.warnings.push(Error::warning(
+                    ErrorKind::UnusedVariable {
+                        name: name.to_string(),
+                    },
+                    span,
+                    self.source,
+                ));
+            }
+        }
+
         AnalysisResult {
             errors: self.errors,
+            warnings: self.warnings,
             analyzed: Analyzed {
                 tree: self.builder.finish(),
                 memory: self.memory,
                 lookup: self.lookup,
+                misnamed: self.misnamed,
             },
         }
     }
 
+    /// Records the types every type alias refers to, to find the aliases referring to themselves
+    /// through the ones after them
+    fn declare_aliases(&mut self, tree: &ParseTree, root: &Node<()>) {
+        for element in root.iter_dfs(tree) {
+            let TreeElement::Node(alias) = element else {
+                continue;
+            };
+            if alias.kind() != TypeAlias {
+                continue;
+            }
+            let mut iter = alias.children_with_leaves(tree);
+            let name = &self.source[iter.next().unwrap().get(tree).span()];
+            let Some(TreeElement::Node(kind)) = iter.next().map(|kind| kind.get(tree)) else {
+                continue;
+            };
+            let names = kind.iter_dfs(tree).filter_map(|e| match e {
+                TreeElement::Leaf(leaf) if leaf.kind() == Identifier => {
+                    Some(&self.source[leaf.span()])
+                }
+                _ => None,
+            });
+            self.aliases.entry(name).or_insert_with(|| names.collect());
+        }
+    }
+
+    /// Extends the chain of aliases back to its first one, if it ends up referring to itself
+    /// through aliases that are not defined yet
+    fn alias_cycle(&self, chain: &mut Vec<&'a str>) -> bool {
+        let last = chain.last().unwrap();
+        for &name in self.aliases.get(last).into_iter().flatten() {
+            if name == chain[0] {
+                chain.push(name);
+                return true;
+            }
+            if self.types.contains_key(name) || chain.contains(&name) {
+                continue;
+            }
+            chain.push(name);
+            if self.alias_cycle(chain) {
+                return true;
+            }
+            chain.pop();
+        }
+        false
+    }
+
+    /// Declares the functions defined by a list of statements so that they can be called before
+    /// their definition
+    fn declare_functions(&mut self, tree: &ParseTree, statements: &[NodeId]) {
+        for &statement in statements {
+            let Some(&fx) = tree.node(statement).children().first() else {
+                continue;
+            };
+            let fx = tree.node(fx);
+            if fx.kind() != Fx {
+                continue;
+            }
+            // the name and the parameters precede the body
+            let mut params = fx.children_with_leaves(tree).collect::<Vec<_>>();
+            params.pop();
+            if result_kind(tree, fx).is_some() {
+                params.pop();
+            }
+            let leaves = params
+                .iter()
+                .filter_map(|e| e.into_leaf())
+                .collect::<Vec<_>>();
+            let Some(name) = leaves.first().map(|&l| tree.leaf(l)) else {
+                continue;
+            };
+            // functions are hoisted, so two of them can't share a name in a scope
+            let previous = self.lookup[*self.scopes.last().unwrap()]
+                .get(&self.source[name.span()])
+                .and_then(|(locs, _)| locs.iter().find(|loc| self.params.contains_key(loc)));
+            if let Some(TreeElement::Node(first)) = previous.map(|&loc| self.memory[loc].syntax) {
+                let first = tree.node(first).children_with_leaves(tree).next().unwrap();
+                self.errors.extend([
+                    Error::error(ErrorKind::AlreadyDefined, name.span(), self.source),
+                    Error::help(
+                        ErrorKind::FirstDefinition,
+                        first.get(tree).span(),
+                        self.source,
+                    ),
+                ]);
+            }
+            self.declared.insert(fx.id(), self.memory.len());
+            self.params
+                .insert(self.memory.len(), self.param_names(tree, &leaves[1..]));
+            self.insert(
+                &self.source[name.span()],
+                Value {
+                    value: None,
+                    // replaced by the name of the function once it is defined
+                    syntax: TreeElement::Node(fx.id()),
+                    type_: MaybeTyped::Typed(self.fx_type(
+                        tree,
+                        &params[1..],
+                        self.return_type(tree, fx),
+                    )),
+                },
+            );
+        }
+    }
+
+    /// Type of a function from the elements of its parameters, the last one collects the extra
+    /// arguments when it follows `...`, the ones followed by `=` have a default value and the ones
+    /// without a kind are numbers
+    fn fx_type(
+        &self,
+        tree: &ParseTree,
+        params: &[TreeElement<NodeId, LeafId>],
+        ret: ValueType,
+    ) -> ValueType {
+        let mut types = Vec::new();
+        let mut variadic = false;
+        let mut params = params.iter();
+        while let Some(&param) = params.next() {
+            let TreeElement::Leaf(param) = param else {
+                continue;
+            };
+            match tree.leaf(param).kind() {
+                Ellipsis => variadic = true,
+                Assign => {
+                    let type_ = types.pop().unwrap();
+                    types.push(ValueType::Default(Box::new(type_)));
+                }
+                Colon => {
+                    let kind = params.next().unwrap().into_node().unwrap();
+                    let type_ = self.kind_type(tree, tree.node(kind));
+                    *types.last_mut().unwrap() = match variadic {
+                        true => ValueType::Variadic(Box::new(type_)),
+                        false => type_,
+                    };
+                }
+                _ if variadic => types.push(ValueType::Variadic(Box::new(ValueType::Number))),
+                _ => types.push(ValueType::Number),
+            }
+        }
+        types.push(ret);
+        ValueType::FnPtr(types)
+    }
+
+    /// Type returned by a function, the ones without a kind are taken to return numbers until their
+    /// body is analyzed
+    fn return_type(&self, tree: &ParseTree, fx: &Node<()>) -> ValueType {
+        match result_kind(tree, fx) {
+            Some(kind) => self.kind_type(tree, tree.node(kind)),
+            None => ValueType::Number,
+        }
+    }
+
+    /// Type named by a kind without analyzing it, unknown types are reported once the kind is
+    /// analyzed
+    fn kind_type(&self, tree: &ParseTree, kind: &Node<()>) -> ValueType {
+        let mut iter = kind.children_with_leaves(tree);
+        match iter.next().unwrap() {
+            TreeElement::Node(inner) if tree.node(inner).kind() == Tuple => ValueType::Tuple(
+                tree.node(inner)
+                    .children()
+                    .iter()
+                    .map(|&kind| self.kind_type(tree, tree.node(kind)))
+                    .collect(),
+            ),
+            TreeElement::Node(inner) => {
+                let inner = self.kind_type(tree, tree.node(inner));
+                let len = iter
+                    .next()
+                    .and_then(|len| len.into_leaf())
+                    .and_then(|len| number(&self.source[tree.leaf(len).span()]).ok())
+                    .map(|len| len as usize);
+                match len {
+                    Some(len) => ValueType::Array(Box::new(inner), len),
+                    None => ValueType::Poisoned,
+                }
+            }
+            TreeElement::Leaf(leaf) if tree.leaf(leaf).kind() == Mul => {
+                match iter.next().and_then(|inner| inner.into_node()) {
+                    Some(inner) => {
+                        ValueType::Pointer(Box::new(self.kind_type(tree, tree.node(inner))))
+                    }
+                    None => ValueType::Poisoned,
+                }
+            }
+            TreeElement::Leaf(leaf) => {
+                let leaf = tree.leaf(leaf);
+                match leaf.kind() {
+                    ByteKw => ValueType::Number,
+                    BoolKw => ValueType::Boolean,
+                    FloatKw => ValueType::Float,
+                    CharKw => ValueType::Char,
+                    SemiColon => ValueType::None,
+                    Identifier => self
+                        .types
+                        .get(&self.source[leaf.span()])
+                        .cloned()
+                        .unwrap_or(ValueType::Poisoned),
+                    _ => ValueType::Poisoned,
+                }
+            }
+        }
+    }
+
+    /// Names of the parameters of a function that can be given by name, the extra arguments can
+    /// only be given by position
+    fn param_names(&self, tree: &ParseTree, params: &[LeafId]) -> Vec<&'a str> {
+        let mut names = Vec::new();
+        for &param in params {
+            match tree.leaf(param).kind() {
+                Ellipsis => break,
+                Identifier => names.push(&self.source[tree.leaf(param).span()]),
+                _ => (),
+            }
+        }
+        names
+    }
+
+    /// Children of a call with the arguments given by name moved to the position of their
+    /// parameter, and whether their names were reported as not matching the parameters
+    fn arguments(
+        &mut self,
+        tree: &ParseTree,
+        node: &Node<()>,
+    ) -> (Vec<TreeElement<NodeId, LeafId>>, bool) {
+        let mut elements = node.children_with_leaves(tree).collect::<Vec<_>>();
+        let named = |e: &TreeElement<NodeId, LeafId>| matches!(e, TreeElement::Node(id) if tree.node(*id).kind() == Field);
+        if !elements.iter().any(named) {
+            return (elements, false);
+        }
+        // the receiver of a method is its first argument
+        let (callee, receiver) = match node.kind() {
+            MethodCall => (1, 1),
+            _ => (0, 0),
+        };
+        let args = elements.split_off(callee + 1);
+        let name = match elements[callee] {
+            TreeElement::Node(id) if tree.node(id).kind() == Value => {
+                tree.node(id).children_with_leaves(tree).next()
+            }
+            callee => Some(callee),
+        };
+        let names = name
+            .and_then(|name| name.into_leaf())
+            .map(|leaf| tree.leaf(leaf))
+            .filter(|leaf| leaf.kind() == Identifier)
+            .and_then(|leaf| self.get_loc(&self.source[leaf.span()]))
+            .and_then(|loc| self.params.get(&loc))
+            .cloned();
+        let Some(names) = names else {
+            self.errors.push(Error::error(
+                ErrorKind::NamedArgsUnsupported,
+                elements[callee].get(tree).span(),
+                self.source,
+            ));
+            // the values are still analyzed in the order they were given
+            elements.extend(args.into_iter().map(|arg| match arg {
+                TreeElement::Node(id) if named(&arg) => {
+                    tree.node(id).children_with_leaves(tree).nth(1).unwrap()
+                }
+                arg => arg,
+            }));
+            return (elements, false);
+        };
+
+        let mut slots = vec![None; names.len().max(receiver + args.len())];
+        let mut position = receiver;
+        let mut by_name = false;
+        let mut misnamed = false;
+        for arg in args {
+            match arg {
+                TreeElement::Node(id) if named(&arg) => {
+                    by_name = true;
+                    let mut iter = tree.node(id).children_with_leaves(tree);
+                    let name = tree.leaf(iter.next().unwrap().into_leaf().unwrap());
+                    let value = iter.next().unwrap();
+                    let kind = match names.iter().position(|&n| n == &self.source[name.span()]) {
+                        Some(i) if slots[i].is_none() => {
+                            slots[i] = Some(value);
+                            continue;
+                        }
+                        Some(_) => ErrorKind::DuplicateArg,
+                        None => ErrorKind::UnknownArg,
+                    };
+                    misnamed = true;
+                    self.errors
+                        .push(Error::error(kind, name.span(), self.source));
+                }
+                arg if by_name => {
+                    misnamed = true;
+                    self.errors.push(Error::error(
+                        ErrorKind::PositionalAfterNamed,
+                        arg.get(tree).span(),
+                        self.source,
+                    ))
+                }
+                arg => {
+                    slots[position] = Some(arg);
+                    position += 1;
+                }
+            }
+        }
+
+        // the arguments are passed by position so none can be left out before a given one,
+        // which is only told apart once the given ones are all valid
+        let given = slots.iter().rposition(|s| s.is_some()).unwrap_or(0);
+        for (i, slot) in slots[..given].iter().enumerate().skip(receiver) {
+            if slot.is_none() && !misnamed {
+                misnamed = true;
+                self.errors.push(Error::error(
+                    ErrorKind::MissingArg {
+                        name: names[i].to_string(),
+                    },
+                    node.span(),
+                    self.source,
+                ));
+            }
+        }
+        elements.extend(slots.into_iter().skip(receiver).flatten());
+        (elements, misnamed)
+    }
+
+    /// Reports the names used by a default value that are neither functions nor the parameters
+    /// before it
+    fn check_default(
+        &mut self,
+        tree: &ParseTree,
+        element: TreeElement<NodeId, LeafId>,
+        params: &[&str],
+    ) {
+        match element {
+            TreeElement::Node(id) => {
+                let node = tree.node(id);
+                // the field of an access is not a name
+                let names = match node.kind() {
+                    Access => 1,
+                    _ => usize::MAX,
+                };
+                for child in node.children_with_leaves(tree).take(names) {
+                    self.check_default(tree, child, params);
+                }
+            }
+            TreeElement::Leaf(id) => {
+                let leaf = tree.leaf(id);
+                let name = &self.source[leaf.span()];
+                if leaf.kind() == Identifier
+                    && !params.contains(&name)
+                    && !matches!(
+                        self.get(name).map(|v| &v.type_),
+                        None | Some(MaybeTyped::Typed(ValueType::FnPtr(_)))
+                    )
+                {
+                    self.errors.push(Error::error(
+                        ErrorKind::NonConstantDefault,
+                        leaf.span(),
+                        self.source,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Analyzes the pattern of a match arm and binds its names in the current scope
+    fn pattern(&mut self, tree: &ParseTree, node: &Node<()>, scrutinee: &MaybeTyped) -> NodeId {
+        self.builder.start_node(node.kind(), node.span().start);
+        let leaves = node.leaves(tree);
+        let mut type_ = scrutinee.clone();
+        match leaves {
+            [number] if number.kind() == Number => {
+                self.analyze_leaf(tree, number);
+                type_ = MaybeTyped::Typed(ValueType::Number);
+            }
+            [binding] => {
+                let id = self.builder.push(binding.kind(), binding.span(), |_| None);
+                self.insert(
+                    &self.source[binding.span()],
+                    Value {
+                        value: None,
+                        syntax: TreeElement::Leaf(id),
+                        type_: scrutinee.clone(),
+                    },
+                );
+            }
+            [name, variant, bindings @ ..] => {
+                self.builder.push(name.kind(), name.span(), |_| None);
+                self.builder.push(variant.kind(), variant.span(), |_| None);
+                let payload = match self.types.get(&self.source[name.span()]) {
+                    Some(t @ ValueType::Enum(_, variants)) => {
+                        type_ = MaybeTyped::Typed(t.clone());
+                        variants
+                            .iter()
+                            .find(|(v, _)| v == &self.source[variant.span()])
+                            .map(|(_, payload)| payload.clone())
+                            .unwrap_or_default()
+                    }
+                    _ => {
+                        self.errors.push(Error::error(
+                            ErrorKind::UnknownType,
+                            name.span(),
+                            self.source,
+                        ));
+                        type_ = MaybeTyped::Typed(ValueType::Poisoned);
+                        Vec::new()
+                    }
+                };
+                for (i, binding) in bindings.iter().enumerate() {
+                    let id = self.builder.push(binding.kind(), binding.span(), |_| None);
+                    self.insert(
+                        &self.source[binding.span()],
+                        Value {
+                            value: None,
+                            syntax: TreeElement::Leaf(id),
+                            type_: MaybeTyped::Typed(
+                                payload.get(i).cloned().unwrap_or(ValueType::Poisoned),
+                            ),
+                        },
+                    );
+                }
+            }
+            _ => (),
+        }
+        self.builder.finish_node(node.span().end, |id| {
+            Some(NodeData::new(NodeKind::Value(Value {
+                value: None,
+                syntax: TreeElement::Node(id),
+                type_,
+            })))
+        })
+    }
+
     fn analyze_element(
         &mut self,
         tree: &ParseTree,
@@ -201,12 +925,12 @@ impl<'a> Analyzer<'a> {
             }
             Scope => {
                 self.builder.start_node(node.kind(), node.span().start);
-                self.lookup.push(HashMap::new());
-                self.current_scope += 1;
+                self.enter_scope();
+                self.declare_functions(tree, node.children());
                 for &child in node.children() {
                     self.analyze_node(tree, tree.node(child));
                 }
-                self.current_scope -= 1;
+                self.exit_scope();
                 self.builder.finish_node(node.span().end, |_| None)
             }
             BinaryOp => {
@@ -287,18 +1011,495 @@ impl<'a> Analyzer<'a> {
             }
             Cast => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
+                // the kind comes first in `<kind> value` and last in `value as kind`
+                let mut type_ = None;
+                for element in node.children_with_leaves(tree) {
+                    let element = self.analyze_element(tree, element);
+                    let element = element.get_from_builder(&self.builder);
+                    if element.kind() == Kind {
+                        type_ = Some(element.type_().clone());
+                    }
+                }
+                let type_ = type_.unwrap();
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_,
+                    })))
+                })
+            }
+            Let => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree).peekable();
+                let mutable = iter.next_if(|e| e.get(tree).kind() == MutKw);
+                if let Some(mutable) = mutable {
+                    let mutable = mutable.get(tree);
+                    self.builder.push(mutable.kind(), mutable.span(), |_| None);
+                }
+                let mut bindings = Vec::new();
+                // opening syntax of the destructured tuple or struct
+                let opening = match iter.next().unwrap() {
+                    TreeElement::Leaf(ident) => {
+                        let ident = tree.leaf(ident);
+                        let id = self.builder.push(ident.kind(), ident.span(), |_| None);
+                        bindings.push((id, &self.source[ident.span()]));
+                        None
+                    }
+                    TreeElement::Node(pattern) => {
+                        let pattern = tree.node(pattern);
+                        self.builder
+                            .start_node(pattern.kind(), pattern.span().start);
+                        let leaves = pattern.leaves(tree);
+                        for leaf in leaves {
+                            let id = self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                            if leaf.kind() == Identifier {
+                                bindings.push((id, &self.source[leaf.span()]));
+                            }
+                        }
+                        self.builder.finish_node(pattern.span().end, |_| None);
+                        let opening = leaves.iter().find(|l| l.kind() != Identifier);
+                        if opening.map(|l| l.kind()) == Some(OpenBrace) {
+                            // the name of the struct is not bound
+                            bindings.remove(0);
+                        }
+                        opening.map(|l| l.kind())
+                    }
+                };
+                let rest = iter.collect::<Vec<_>>();
+                let (value, kind) = rest.split_last().unwrap();
+                // the annotated type takes precedence over the one of the value
+                let kind = kind.first().map(|&kind| {
+                    self.analyze_element(tree, kind)
+                        .get_from_builder(&self.builder)
+                        .type_()
+                        .clone()
+                });
                 let type_ = self
-                    .analyze_element(tree, iter.next().unwrap())
-                    .into_node()
-                    .unwrap()
+                    .analyze_element(tree, *value)
                     .get_from_builder(&self.builder)
-                    .data()
-                    .as_ref()
-                    .unwrap()
                     .type_()
                     .clone();
+                let type_ = kind.unwrap_or(type_);
+                for (i, (id, ident)) in bindings.into_iter().enumerate() {
+                    let type_ = match (opening, &type_) {
+                        (None, t) => t.clone(),
+                        (Some(opening), MaybeTyped::Typed(t)) => MaybeTyped::Typed(
+                            match opening {
+                                OpenParen => t.field(&i.to_string()),
+                                _ => t.field(ident),
+                            }
+                            .unwrap_or(ValueType::Poisoned),
+                        ),
+                        _ => MaybeTyped::Typed(ValueType::Poisoned),
+                    };
+                    let span = self.builder.leaf(id).span();
+                    if mutable.is_none() {
+                        self.immutable.insert(self.memory.len(), span.clone());
+                    }
+                    self.bindings.push((self.memory.len(), span));
+                    self.insert(
+                        ident,
+                        Value {
+                            value: None,
+                            syntax: TreeElement::Leaf(id),
+                            type_,
+                        },
+                    );
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            Const => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                let id = self.builder.push(ident.kind(), ident.span(), |_| None);
+                // the kind, or else the value, tells a boolean constant from a number
+                let mut type_ = None;
+                for element in iter {
+                    let element = self.analyze_element(tree, element);
+                    type_ = type_.or_else(|| {
+                        element
+                            .get_from_builder(&self.builder)
+                            .type_()
+                            .type_()
+                            .cloned()
+                    });
+                }
+                let type_ = match type_ {
+                    Some(ValueType::Boolean) => ValueType::Boolean,
+                    _ => ValueType::Number,
+                };
+                // the type checker makes sure the value is known at compile time
+                self.constants.insert(self.memory.len());
+                self.insert(
+                    &self.source[ident.span()],
+                    Value {
+                        value: None,
+                        syntax: TreeElement::Leaf(id),
+                        type_: MaybeTyped::Typed(type_),
+                    },
+                );
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            ReLet => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let lhs = self
+                    .analyze_element(tree, iter.next().unwrap())
+                    .into_node()
+                    .unwrap();
+                self.analyze_element(tree, iter.next().unwrap());
                 self.analyze_element(tree, iter.next().unwrap());
+                let lhs = lhs.get_from_builder(&self.builder);
+                let data_lhs = lhs.data().as_ref().unwrap();
+                // elements and fields are assigned through the variable holding them
+                let name = match data_lhs.assignable {
+                    AssignLHS::Index(_) | AssignLHS::Field(_) => self.place_root(lhs.id()),
+                    _ => Some(&self.source[lhs.span()]),
+                };
+                let immutable = name
+                    .and_then(|name| self.get_loc(name))
+                    .and_then(|loc| self.immutable.get(&loc));
+                match (&data_lhs.assignable, immutable) {
+                    (AssignLHS::Invalid, _) => self.errors.push(Error::error(
+                        ErrorKind::InvalidLHS,
+                        lhs.span(),
+                        self.source,
+                    )),
+                    (AssignLHS::Deref(_), _) => (),
+                    (_, Some(binding)) => self.errors.extend([
+                        Error::error(
+                            ErrorKind::ImmutableAssignment {
+                                name: name.unwrap().to_string(),
+                            },
+                            lhs.span(),
+                            self.source,
+                        ),
+                        Error::help(ErrorKind::DeclaredImmutable, binding.clone(), self.source),
+                    ]),
+                    _ => (),
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            Pointer => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let type_ = match self
+                    .analyze_element(tree, node.children_with_leaves(tree).next().unwrap())
+                    .get_from_builder(&self.builder)
+                    .type_()
+                {
+                    MaybeTyped::Typed(t) => {
+                        MaybeTyped::Typed(ValueType::Pointer(Box::new(t.clone())))
+                    }
+                    // inferred later as a pointer to the type of the value
+                    _ => MaybeTyped::UnTyped(TreeElement::Node(node.id())),
+                };
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_,
+                    })))
+                })
+            }
+            List => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let elements = node
+                    .children()
+                    .iter()
+                    .map(|&child| self.analyze_node(tree, tree.node(child)))
+                    .collect::<Vec<_>>();
+                let type_ = match elements.first() {
+                    Some(&a) => self
+                        .builder
+                        .node(a)
+                        .data()
+                        .as_ref()
+                        .unwrap()
+                        .type_()
+                        .clone(),
+                    None => MaybeTyped::Typed(ValueType::None),
+                }
+                .map(|t| ValueType::Array(Box::new(t), elements.len()));
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: match type_ {
+                            MaybeTyped::UnTyped(_) => {
+                                MaybeTyped::UnTyped(TreeElement::Node(node.id()))
+                            }
+                            t => t,
+                        },
+                    })))
+                })
+            }
+            Tuple => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut types = Some(Vec::new());
+                for &child in node.children() {
+                    let element = self.analyze_node(tree, tree.node(child));
+                    let element = self.builder.node(element).data().as_ref().unwrap().type_();
+                    match (&mut types, element) {
+                        (Some(types), MaybeTyped::Typed(t)) => types.push(t.clone()),
+                        _ => types = None,
+                    }
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: match types {
+                            Some(types) => MaybeTyped::Typed(ValueType::Tuple(types)),
+                            None => MaybeTyped::UnTyped(TreeElement::Node(node.id())),
+                        },
+                    })))
+                })
+            }
+            Struct => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut name = None;
+                let mut fields: Vec<(std::string::String, ValueType)> = Vec::new();
+                for element in node.children_with_leaves(tree) {
+                    match element {
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                            if leaf.kind() == Identifier {
+                                name = Some(leaf.span());
+                            }
+                        }
+                        TreeElement::Node(id) => {
+                            let id = self.analyze_node(tree, tree.node(id));
+                            let Some((span, value)) = self.field(id) else {
+                                continue;
+                            };
+                            let field = &self.source[span.clone()];
+                            if fields.iter().any(|(f, _)| f == field) {
+                                self.errors.push(Error::error(
+                                    ErrorKind::AlreadyDefined,
+                                    span,
+                                    self.source,
+                                ));
+                                continue;
+                            }
+                            let type_ = value.get_from_builder(&self.builder).type_();
+                            let type_ = type_.type_().cloned().unwrap_or(ValueType::Poisoned);
+                            fields.push((field.to_string(), type_));
+                        }
+                    }
+                }
+                if let Some(span) = name {
+                    let name = &self.source[span.clone()];
+                    if self.types.contains_key(name) {
+                        self.errors.push(Error::error(
+                            ErrorKind::AlreadyDefined,
+                            span,
+                            self.source,
+                        ));
+                    } else {
+                        self.types
+                            .insert(name, ValueType::Struct(name.to_string(), fields));
+                    }
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            FileKw => {
+                // the included files are already part of the source
+                self.builder.start_node(node.kind(), node.span().start);
+                for leaf in node.leaves(tree) {
+                    self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            TypeAlias => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let name = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                self.builder.push(name.kind(), name.span(), |_| None);
+                let alias = &self.source[name.span()];
+                // aliases are resolved in order, so they can't refer to themselves
+                let mut chain = vec![alias];
+                if !self.cyclic.contains(alias) && self.alias_cycle(&mut chain) {
+                    self.errors.push(Error::error(
+                        ErrorKind::CyclicAlias {
+                            chain: chain.iter().map(|name| name.to_string()).collect(),
+                        },
+                        name.span(),
+                        self.source,
+                    ));
+                    self.cyclic.extend(chain);
+                }
+                let type_ = match iter.next() {
+                    Some(kind) => {
+                        let kind = self.analyze_element(tree, kind);
+                        let type_ = kind.get_from_builder(&self.builder).type_();
+                        type_.type_().cloned().unwrap_or(ValueType::Poisoned)
+                    }
+                    None => ValueType::Poisoned,
+                };
+                if self.types.contains_key(alias) {
+                    self.errors.push(Error::error(
+                        ErrorKind::AlreadyDefined,
+                        name.span(),
+                        self.source,
+                    ));
+                } else {
+                    self.types.insert(alias, type_);
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            StructLit => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut type_ = ValueType::Poisoned;
+                for element in node.children_with_leaves(tree) {
+                    match element {
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                            if leaf.kind() != Identifier {
+                                continue;
+                            }
+                            match self.types.get(&self.source[leaf.span()]) {
+                                Some(t) => type_ = t.clone(),
+                                None => self.errors.push(Error::error(
+                                    ErrorKind::UnknownType,
+                                    leaf.span(),
+                                    self.source,
+                                )),
+                            }
+                        }
+                        TreeElement::Node(id) => {
+                            self.analyze_node(tree, tree.node(id));
+                        }
+                    }
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(type_),
+                    })))
+                })
+            }
+            Enum => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut name = None;
+                let mut variants: Vec<(std::string::String, Vec<ValueType>)> = Vec::new();
+                for element in node.children_with_leaves(tree) {
+                    match element {
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                            if leaf.kind() == Identifier {
+                                name = Some(leaf.span());
+                            }
+                        }
+                        TreeElement::Node(id) => {
+                            let id = self.analyze_node(tree, tree.node(id));
+                            let mut iter = self
+                                .builder
+                                .node(id)
+                                .children_with_leaves_builder(&self.builder);
+                            let Some(TreeElement::Leaf(variant)) = iter.next() else {
+                                continue;
+                            };
+                            let payload = iter
+                                .map(|kind| {
+                                    let type_ = kind.get_from_builder(&self.builder).type_();
+                                    type_.type_().cloned().unwrap_or(ValueType::Poisoned)
+                                })
+                                .collect();
+                            let span = self.builder.leaf(variant).span();
+                            let variant = &self.source[span.clone()];
+                            if variants.iter().any(|(v, _)| v == variant) {
+                                self.errors.push(Error::error(
+                                    ErrorKind::AlreadyDefined,
+                                    span,
+                                    self.source,
+                                ));
+                                continue;
+                            }
+                            variants.push((variant.to_string(), payload));
+                        }
+                    }
+                }
+                if let Some(span) = name {
+                    let name = &self.source[span.clone()];
+                    if self.types.contains_key(name) {
+                        self.errors.push(Error::error(
+                            ErrorKind::AlreadyDefined,
+                            span,
+                            self.source,
+                        ));
+                    } else {
+                        self.types
+                            .insert(name, ValueType::Enum(name.to_string(), variants));
+                    }
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            EnumLit => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut type_ = ValueType::Poisoned;
+                for (i, element) in node.children_with_leaves(tree).enumerate() {
+                    match element {
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                            if i != 0 {
+                                continue;
+                            }
+                            match self.types.get(&self.source[leaf.span()]) {
+                                Some(t @ ValueType::Enum(..)) => type_ = t.clone(),
+                                _ => self.errors.push(Error::error(
+                                    ErrorKind::UnknownType,
+                                    leaf.span(),
+                                    self.source,
+                                )),
+                            }
+                        }
+                        TreeElement::Node(id) => {
+                            self.analyze_node(tree, tree.node(id));
+                        }
+                    }
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(type_),
+                    })))
+                })
+            }
+            Match => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let scrutinee = self.analyze_element(tree, iter.next().unwrap());
+                let scrutinee = scrutinee.get_from_builder(&self.builder).type_().clone();
+                let mut type_ = None;
+                for arm in iter {
+                    let arm = arm.into_node().unwrap().get(tree);
+                    self.builder.start_node(arm.kind(), arm.span().start);
+                    let mut iter = arm.children_with_leaves(tree);
+                    // the bindings of the pattern are only visible inside the arm
+                    self.enter_scope();
+                    let pattern = iter.next().unwrap().into_node().unwrap().get(tree);
+                    self.pattern(tree, pattern, &scrutinee);
+                    if let Some(value) = iter.next() {
+                        let value = self.analyze_element(tree, value);
+                        let value = value.get_from_builder(&self.builder).type_().clone();
+                        type_.get_or_insert(value);
+                    }
+                    self.exit_scope();
+                    self.builder.finish_node(arm.span().end, |_| None);
+                }
+                let type_ = match type_ {
+                    Some(MaybeTyped::Typed(t)) => MaybeTyped::Typed(t),
+                    Some(_) => MaybeTyped::UnTyped(TreeElement::Node(node.id())),
+                    None => MaybeTyped::Typed(ValueType::None),
+                };
                 self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
                         value: None,
@@ -307,63 +1508,78 @@ impl<'a> Analyzer<'a> {
                     })))
                 })
             }
-            Let => {
+            Access => {
                 self.builder.start_node(node.kind(), node.span().start);
                 let mut iter = node.children_with_leaves(tree);
-                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
-                let id = self.builder.push(ident.kind(), ident.span(), |_| None);
-                let ident = &self.source[ident.span()];
-                let type_ = self
-                    .analyze_element(tree, iter.next().unwrap())
-                    .get_from_builder(&self.builder)
-                    .type_()
-                    .clone();
-                self.insert(
-                    ident,
-                    Value {
-                        value: None,
-                        syntax: TreeElement::Leaf(id),
-                        type_,
-                    },
-                );
-                self.builder.finish_node(node.span().end, |_| None)
+                let object = self.analyze_element(tree, iter.next().unwrap());
+                let field = iter.next().map(|field| {
+                    let field = field.into_leaf().unwrap().get(tree);
+                    self.builder.push(field.kind(), field.span(), |_| None);
+                    &self.source[field.span()]
+                });
+                let type_ = match object.get_from_builder(&self.builder).type_() {
+                    MaybeTyped::Typed(t) => MaybeTyped::Typed(
+                        field
+                            .and_then(|field| t.field(field))
+                            .unwrap_or(ValueType::Poisoned),
+                    ),
+                    _ => MaybeTyped::UnTyped(TreeElement::Node(node.id())),
+                };
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(
+                        NodeData::new(NodeKind::Value(Value {
+                            value: None,
+                            syntax: TreeElement::Node(id),
+                            type_,
+                        }))
+                        .assignable(AssignLHS::Field(TreeElement::Node(id))),
+                    )
+                })
             }
-            ReLet => {
+            Field | Variant => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
-                let lhs = self
-                    .analyze_element(tree, iter.next().unwrap())
-                    .into_node()
-                    .unwrap();
-                self.analyze_element(tree, iter.next().unwrap());
-                self.analyze_element(tree, iter.next().unwrap());
-                let lhs = lhs.get_from_builder(&self.builder);
-                let data_lhs = lhs.data().as_ref().unwrap();
-                if data_lhs.assignable == AssignLHS::Invalid {
-                    self.errors
-                        .push(Error::error(ErrorKind::InvalidLHS, lhs.span(), self.source));
+                for element in node.children_with_leaves(tree) {
+                    match element {
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                        }
+                        TreeElement::Node(id) => {
+                            self.analyze_node(tree, tree.node(id));
+                        }
+                    }
                 }
                 self.builder.finish_node(node.span().end, |_| None)
             }
-            Pointer => {
+            Index => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let type_ = self
-                    .analyze_element(tree, node.children_with_leaves(tree).next().unwrap())
-                    .get_from_builder(&self.builder)
-                    .type_()
-                    .clone()
-                    .map(|v| ValueType::Pointer(Box::new(v)));
+                let mut iter = node.children_with_leaves(tree);
+                let object = self.analyze_element(tree, iter.next().unwrap());
+                self.analyze_element(tree, iter.next().unwrap());
+                let type_ = match object.get_from_builder(&self.builder).type_() {
+                    MaybeTyped::Typed(t) => {
+                        MaybeTyped::Typed(t.index().unwrap_or(ValueType::Poisoned))
+                    }
+                    _ => MaybeTyped::UnTyped(TreeElement::Node(node.id())),
+                };
                 self.builder.finish_node(node.span().end, |id| {
-                    Some(NodeData::new(NodeKind::Value(Value {
-                        value: None,
-                        syntax: TreeElement::Node(id),
-                        type_,
-                    })))
+                    Some(
+                        NodeData::new(NodeKind::Value(Value {
+                            value: None,
+                            syntax: TreeElement::Node(id),
+                            type_,
+                        }))
+                        .assignable(AssignLHS::Index(TreeElement::Node(id))),
+                    )
                 })
             }
             Loop => {
                 self.builder.start_node(node.kind(), node.span().start);
+                let label = self.label(tree, node);
                 let mut elements = node.children_with_leaves(tree).collect::<Vec<_>>();
+                if label.is_some() {
+                    self.analyze_element(tree, elements.remove(0));
+                }
                 let body = elements.pop().unwrap();
                 let mut iter = elements.split(|s| {
                     s.into_leaf()
@@ -405,7 +1621,125 @@ impl<'a> Analyzer<'a> {
                         })))
                     });
                 }
+                self.loops.push(label.map(|(label, _)| label));
+                self.analyze_element(tree, body);
+                self.loops.pop();
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            While => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let label = self.label(tree, node);
+                let (&body, children) = node.children().split_last().unwrap();
+                for &child in children {
+                    self.analyze_node(tree, tree.node(child));
+                }
+                self.loops.push(label.map(|(label, _)| label));
+                self.analyze_node(tree, tree.node(body));
+                self.loops.pop();
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            For => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let label = self.label(tree, node);
+                let mut iter = node
+                    .children_with_leaves(tree)
+                    .skip(label.is_some() as usize);
+                if label.is_some() {
+                    self.analyze_node(tree, tree.node(node.children()[0]));
+                }
+                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                let id = self.builder.push(ident.kind(), ident.span(), |_| None);
+                let ident = &self.source[ident.span()];
+                let start = iter.next().unwrap();
+                let end = iter.next().unwrap();
+                let body = iter.next().unwrap();
+                self.analyze_element(tree, start);
+                self.analyze_element(tree, end);
+                // the induction variable lives in its own scope around the body
+                self.enter_scope();
+                self.insert(
+                    ident,
+                    Value {
+                        value: None,
+                        syntax: TreeElement::Leaf(id),
+                        type_: MaybeTyped::Typed(ValueType::Number),
+                    },
+                );
+                self.loops.push(label.map(|(label, _)| label));
                 self.analyze_element(tree, body);
+                self.loops.pop();
+                self.exit_scope();
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            Label => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for element in node.children_with_leaves(tree) {
+                    match element {
+                        TreeElement::Node(id) => {
+                            self.analyze_node(tree, tree.node(id));
+                        }
+                        TreeElement::Leaf(id) => {
+                            let leaf = tree.leaf(id);
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                        }
+                    }
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            ReturnKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for &child in node.children() {
+                    let value = self.analyze_node(tree, tree.node(child));
+                    let value = self.builder.node(value).data().as_ref().map(|d| d.type_());
+                    if let (Some(returned @ None), Some(MaybeTyped::Typed(type_))) =
+                        (self.returned.last_mut(), value)
+                    {
+                        *returned = Some(type_.clone());
+                    }
+                }
+                if self.fx_depth == 0 {
+                    self.errors.push(Error::error(
+                        ErrorKind::KeywordMisuse { keyword: ReturnKw },
+                        node.span(),
+                        self.source,
+                    ));
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            AssertKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for &child in node.children() {
+                    self.analyze_node(tree, tree.node(child));
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            BreakKw | ContinueKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for element in node.children_with_leaves(tree) {
+                    self.analyze_element(tree, element);
+                }
+                match self.label(tree, node) {
+                    // the translator relies on the label being found
+                    Some((label, span)) if !self.loops.contains(&Some(label)) => {
+                        self.errors.push(Error::error(
+                            ErrorKind::UnknownLabel {
+                                label: label.to_string(),
+                            },
+                            span,
+                            self.source,
+                        ));
+                    }
+                    None if self.loops.is_empty() => {
+                        self.errors.push(Error::error(
+                            ErrorKind::KeywordMisuse {
+                                keyword: node.kind(),
+                            },
+                            node.span(),
+                            self.source,
+                        ));
+                    }
+                    _ => (),
+                }
                 self.builder.finish_node(node.span().end, |_| None)
             }
             If => {
@@ -447,54 +1781,221 @@ impl<'a> Analyzer<'a> {
                     })))
                 })
             }
-            Call => {
+            Call | MethodCall => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
-                let f = self.analyze_element(tree, iter.next().unwrap());
-                iter.for_each(|v| {
-                    self.analyze_element(tree, v);
-                });
-                let type_ = if let MaybeTyped::Typed(ValueType::FnPtr(v)) =
+                let mut elements = Vec::new();
+                let (arguments, misnamed) = self.arguments(tree, node);
+                for element in arguments {
+                    elements.push(self.analyze_element(tree, element));
+                }
+                if node.kind() == MethodCall {
+                    // the receiver is passed as the first argument
+                    elements.swap(0, 1);
+                }
+                let f = elements[0];
+                let type_ = if let MaybeTyped::Typed(ValueType::FnPtr(v) | ValueType::Closure(v)) =
                     f.get_from_builder(&self.builder).type_()
                 {
                     MaybeTyped::Typed(v.last().cloned().unwrap())
                 } else {
                     MaybeTyped::UnTyped(TreeElement::Node(node.id()))
                 };
-                self.builder.finish_node(node.span().end, |id| {
+                let id = self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
                         value: None,
                         syntax: TreeElement::Node(id),
                         type_,
                     })))
-                })
+                });
+                if misnamed {
+                    self.misnamed.insert(id);
+                }
+                id
             }
             Fx => {
                 self.builder.start_node(node.kind(), node.span().start);
-                self.lookup.push(HashMap::new());
-                self.current_scope += 1;
                 let mut iter = node.children_with_leaves(tree);
                 let name = iter.next().unwrap().into_leaf().unwrap().get(tree);
-                self.builder.push(name.kind(), name.span(), |_| None);
-                let mut args = iter.collect::<Vec<_>>();
-                let body = args.pop().unwrap();
-                for arg in args {
-                    let arg = arg.into_leaf().unwrap().get(tree);
-                    let id = self.builder.push(arg.kind(), arg.span(), |_| None);
+                let id = self.builder.push(name.kind(), name.span(), |_| None);
+                let name = &self.source[name.span()];
+                let mut params = iter.collect::<Vec<_>>();
+                let body = params.pop().unwrap();
+                let kind = result_kind(tree, node).map(|_| params.pop().unwrap());
+                let leaves = params
+                    .iter()
+                    .filter_map(|param| param.into_leaf())
+                    .collect::<Vec<_>>();
+                let type_ = self.fx_type(tree, &params, self.return_type(tree, node));
+                let loc = match self.declared.remove(&node.id()) {
+                    // the kinds may name types defined after the declaration
+                    Some(loc) => {
+                        self.memory[loc].syntax = TreeElement::Leaf(id);
+                        self.memory[loc].type_ = MaybeTyped::Typed(type_.clone());
+                        loc
+                    }
+                    // the function is defined before its body so that it can call itself
+                    None => {
+                        let loc = self.memory.len();
+                        self.params.insert(loc, self.param_names(tree, &leaves));
+                        self.insert(
+                            name,
+                            Value {
+                                value: None,
+                                syntax: TreeElement::Leaf(id),
+                                type_: MaybeTyped::Typed(type_.clone()),
+                            },
+                        );
+                        loc
+                    }
+                };
+                self.enter_scope();
+                let frame = self.memory.len();
+                // the function body is not part of the loops around it
+                let loops = std::mem::take(&mut self.loops);
+                self.fx_depth += 1;
+                let ValueType::FnPtr(types) = type_ else {
+                    unreachable!()
+                };
+                let mut types = types.into_iter();
+                let mut names = Vec::new();
+                let mut defaulted = false;
+                let mut params = params.into_iter().peekable();
+                while let Some(param) = params.next() {
+                    let param = param.into_leaf().unwrap().get(tree);
+                    let id = self.builder.push(param.kind(), param.span(), |_| None);
+                    if param.kind() == Ellipsis {
+                        continue;
+                    }
+                    if let Some(colon) = params.next_if(|p| p.get(tree).kind() == Colon) {
+                        let colon = colon.get(tree);
+                        self.builder.push(colon.kind(), colon.span(), |_| None);
+                        self.analyze_element(tree, params.next().unwrap());
+                    }
+                    let type_ = match types.next().unwrap() {
+                        // the default value is analyzed before its parameter is defined
+                        ValueType::Default(type_) => {
+                            let assign = params.next().unwrap().into_leaf().unwrap().get(tree);
+                            self.builder.push(assign.kind(), assign.span(), |_| None);
+                            let default = params.next().unwrap();
+                            self.analyze_element(tree, default);
+                            self.check_default(tree, default, &names);
+                            defaulted = true;
+                            *type_
+                        }
+                        type_ if defaulted && !matches!(type_, ValueType::Variadic(_)) => {
+                            self.errors.push(Error::error(
+                                ErrorKind::DefaultBeforeRequired,
+                                param.span(),
+                                self.source,
+                            ));
+                            type_
+                        }
+                        type_ => type_,
+                    };
+                    names.push(&self.source[param.span()]);
                     self.insert(
-                        &self.source[arg.span()],
+                        &self.source[param.span()],
                         Value {
                             value: None,
                             syntax: TreeElement::Leaf(id),
                             // type_: MaybeTyped::UnTyped(TreeElement::Leaf(id)),
-                            type_: MaybeTyped::Typed(ValueType::Number),
+                            type_: MaybeTyped::Typed(type_),
                         },
                     );
                 }
-                self.analyze_element(tree, body);
-                self.current_scope -= 1;
+                // the default values report the variables they can't use themselves
+                self.frames.push(frame);
+                if let Some(kind) = kind {
+                    self.analyze_element(tree, kind);
+                }
+                self.returned.push(None);
+                let body = self.analyze_element(tree, body);
+                let returned = self.returned.pop().unwrap();
+                // functions without a kind return the value of their body, the type checker makes
+                // sure their returns agree with it
+                if kind.is_none() {
+                    let ret = self
+                        .result_type(body)
+                        .or(returned)
+                        .unwrap_or(ValueType::Number);
+                    if let MaybeTyped::Typed(ValueType::FnPtr(types)) = &mut self.memory[loc].type_
+                    {
+                        *types.last_mut().unwrap() = ret;
+                    }
+                }
+                self.fx_depth -= 1;
+                self.loops = loops;
+                self.frames.pop();
+                self.exit_scope();
                 self.builder.finish_node(node.span().end, |_| None)
             }
+            Lambda => {
+                self.builder.start_node(node.kind(), node.span().start);
+                self.enter_scope();
+                self.captures.push((self.memory.len(), Vec::new()));
+                let mut params = node.children_with_leaves(tree).collect::<Vec<_>>();
+                let body = params.pop().unwrap();
+                let mut types = Vec::new();
+                for param in params {
+                    let param = tree.node(param.into_node().unwrap());
+                    self.builder.start_node(param.kind(), param.span().start);
+                    let mut iter = param.children_with_leaves(tree);
+                    let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                    let id = self.builder.push(ident.kind(), ident.span(), |_| None);
+                    let type_ = match iter.next() {
+                        Some(kind) => self
+                            .analyze_element(tree, kind)
+                            .get_from_builder(&self.builder)
+                            .type_()
+                            .clone(),
+                        None => MaybeTyped::Typed(ValueType::Number),
+                    };
+                    self.builder.finish_node(param.span().end, |_| None);
+                    types.push(type_.type_().cloned().unwrap_or(ValueType::Poisoned));
+                    self.insert(
+                        &self.source[ident.span()],
+                        Value {
+                            value: None,
+                            syntax: TreeElement::Leaf(id),
+                            type_,
+                        },
+                    );
+                }
+                // the lambda body is not part of the loops around it
+                let loops = std::mem::take(&mut self.loops);
+                self.fx_depth += 1;
+                self.returned.push(None);
+                let body = self.analyze_element(tree, body);
+                self.returned.pop();
+                self.fx_depth -= 1;
+                self.loops = loops;
+                self.exit_scope();
+                let (_, captures) = self.captures.pop().unwrap();
+                self.builder.start_node(Capture, node.span().end);
+                for span in captures {
+                    self.builder.push(Identifier, span, |_| None);
+                }
+                self.builder.finish_node(node.span().end, |_| None);
+                let ret = body
+                    .get_from_builder(&self.builder)
+                    .type_()
+                    .type_()
+                    .cloned();
+                self.builder.finish_node(node.span().end, |id| {
+                    let type_ = match ret {
+                        Some(t) => {
+                            types.push(t);
+                            MaybeTyped::Typed(ValueType::Closure(types))
+                        }
+                        None => MaybeTyped::UnTyped(TreeElement::Node(id)),
+                    };
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_,
+                    })))
+                })
+            }
             Value => {
                 self.builder.start_node(node.kind(), node.span().start);
                 let a = self
@@ -517,25 +2018,82 @@ impl<'a> Analyzer<'a> {
             }
             Kind => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let type_ = match node
-                    .children_with_leaves(tree)
-                    .next()
-                    .unwrap()
-                    .into_leaf()
-                    .unwrap()
-                    .get(tree)
-                    .kind()
-                {
-                    ByteKw => MaybeTyped::Typed(ValueType::Number),
-                    SemiColon => MaybeTyped::Typed(ValueType::None),
-                    Identifier => todo!(),
-                    _ => unreachable!(),
+                let mut iter = node.children_with_leaves(tree);
+                let type_ = match iter.next().unwrap() {
+                    TreeElement::Node(inner) if tree.node(inner).kind() == Tuple => {
+                        let inner = self.analyze_node(tree, tree.node(inner));
+                        let inner = self.builder.node(inner).data().as_ref().unwrap().type_();
+                        inner.type_().cloned().unwrap_or(ValueType::Poisoned)
+                    }
+                    TreeElement::Node(inner) => {
+                        let inner = self.analyze_node(tree, tree.node(inner));
+                        let inner = self.builder.node(inner).data().as_ref().unwrap().type_();
+                        let inner = inner.type_().cloned();
+                        let len = iter.next().and_then(|len| {
+                            match &**self
+                                .analyze_element(tree, len)
+                                .get_from_builder(&self.builder)
+                                .into_leaf()?
+                                .data()
+                                .as_ref()?
+                            {
+                                LeafKind::Value(Value {
+                                    value: Some(ValueData::Number(n)),
+                                    ..
+                                }) => Some(*n as usize),
+                                _ => None,
+                            }
+                        });
+                        for element in iter {
+                            self.analyze_element(tree, element);
+                        }
+                        match (inner, len) {
+                            (Some(t), Some(len)) => ValueType::Array(Box::new(t), len),
+                            _ => ValueType::Poisoned,
+                        }
+                    }
+                    TreeElement::Leaf(leaf) if tree.leaf(leaf).kind() == Mul => {
+                        let leaf = tree.leaf(leaf);
+                        self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                        let inner = self.analyze_element(tree, iter.next().unwrap());
+                        let inner = inner.get_from_builder(&self.builder).type_();
+                        match inner.type_() {
+                            Some(t) => ValueType::Pointer(Box::new(t.clone())),
+                            None => ValueType::Poisoned,
+                        }
+                    }
+                    TreeElement::Leaf(leaf) => {
+                        let leaf = tree.leaf(leaf);
+                        self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                        match leaf.kind() {
+                            ByteKw => ValueType::Number,
+                            BoolKw => ValueType::Boolean,
+                            FloatKw => ValueType::Float,
+                            CharKw => ValueType::Char,
+                            SemiColon => ValueType::None,
+                            Identifier if self.types.contains_key(&self.source[leaf.span()]) => {
+                                self.types[&self.source[leaf.span()]].clone()
+                            }
+                            // the cycle is reported at the alias starting it
+                            Identifier if self.cyclic.contains(&self.source[leaf.span()]) => {
+                                ValueType::Poisoned
+                            }
+                            _ => {
+                                self.errors.push(Error::error(
+                                    ErrorKind::UnknownType,
+                                    leaf.span(),
+                                    self.source,
+                                ));
+                                ValueType::Poisoned
+                            }
+                        }
+                    }
                 };
                 self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
                         value: None,
                         syntax: TreeElement::Node(id),
-                        type_,
+                        type_: MaybeTyped::Typed(type_),
                     })))
                 })
             }
@@ -558,21 +2116,57 @@ impl<'a> Analyzer<'a> {
 
     fn analyze_leaf(&mut self, _tree: &ParseTree, leaf: &Leaf<SyntaxKind>) -> LeafId {
         match leaf.kind() {
-            Add | Mul | Div | Sub | Mod | And | Or | Not | Shl | Shr | Equal | NotEqual | Xor
-            | LessThan | LessEqual | GreaterThan | GreaterEqual | Assign => {
+            Add | Mul | Pow | Div | Sub | Mod | And | Or | Not | Shl | Shr | Equal | NotEqual
+            | Xor | LessThan | LessEqual | GreaterThan | GreaterEqual | Assign => {
                 self.builder.push(leaf.kind(), leaf.span(), |_| None)
             }
             AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | AndAssign | OrAssign
             | XorAssign | ShlAssign | ShrAssign => {
                 self.builder.push(leaf.kind(), leaf.span(), |_| None)
             }
-            Number => self.builder.push(leaf.kind(), leaf.span(), |id| {
-                Some(LeafData::new(LeafKind::Value(Value {
-                    value: Some(ValueData::Number(self.source[leaf.span()].parse().unwrap())),
-                    syntax: TreeElement::Leaf(id),
-                    type_: MaybeTyped::Typed(ValueType::Number),
-                })))
-            }),
+            BreakKw | ContinueKw => self.builder.push(leaf.kind(), leaf.span(), |_| None),
+            Number => {
+                let value = match number(&self.source[leaf.span()]) {
+                    Ok(n) => Some(ValueData::Number(n)),
+                    Err((kind, span)) => {
+                        let start = leaf.span().start;
+                        self.errors.push(Error::error(
+                            kind,
+                            start + span.start..start + span.end,
+                            self.source,
+                        ));
+                        None
+                    }
+                };
+                self.builder.push(leaf.kind(), leaf.span(), |id| {
+                    Some(LeafData::new(LeafKind::Value(Value {
+                        value,
+                        syntax: TreeElement::Leaf(id),
+                        type_: MaybeTyped::Typed(ValueType::Number),
+                    })))
+                })
+            }
+            Float => {
+                let value = match float(&self.source[leaf.span()]) {
+                    Ok(n) => Some(ValueData::Float(n)),
+                    Err(span) => {
+                        let start = leaf.span().start;
+                        self.errors.push(Error::error(
+                            ErrorKind::MisplacedSeparator,
+                            start + span.start..start + span.end,
+                            self.source,
+                        ));
+                        None
+                    }
+                };
+                self.builder.push(leaf.kind(), leaf.span(), |id| {
+                    Some(LeafData::new(LeafKind::Value(Value {
+                        value,
+                        syntax: TreeElement::Leaf(id),
+                        type_: MaybeTyped::Typed(ValueType::Float),
+                    })))
+                })
+            }
             SemiColon => self.builder.push(leaf.kind(), leaf.span(), |id| {
                 Some(LeafData::new(LeafKind::Value(Value {
                     value: Some(ValueData::None),
@@ -580,13 +2174,28 @@ impl<'a> Analyzer<'a> {
                     type_: MaybeTyped::Typed(ValueType::None),
                 })))
             }),
-            InKw => self.builder.push(leaf.kind(), leaf.span(), |id| {
+            TrueKw | FalseKw => self.builder.push(leaf.kind(), leaf.span(), |id| {
                 Some(LeafData::new(LeafKind::Value(Value {
-                    value: None,
+                    value: Some(ValueData::Bool(leaf.kind() == TrueKw)),
                     syntax: TreeElement::Leaf(id),
-                    type_: MaybeTyped::Typed(ValueType::Number),
+                    type_: MaybeTyped::Typed(ValueType::Boolean),
                 })))
             }),
+            InKw => {
+                // `in` only separates the variable of a `for` loop from its range
+                self.errors.push(Error::error(
+                    ErrorKind::KeywordMisuse { keyword: InKw },
+                    leaf.span(),
+                    self.source,
+                ));
+                self.builder.push(leaf.kind(), leaf.span(), |id| {
+                    Some(LeafData::new(LeafKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Leaf(id),
+                        type_: MaybeTyped::Typed(ValueType::Poisoned),
+                    })))
+                })
+            }
             Error => self.builder.push(leaf.kind(), leaf.span(), |id| {
                 Some(LeafData::new(LeafKind::Value(Value {
                     value: None,
@@ -597,6 +2206,7 @@ impl<'a> Analyzer<'a> {
             Char => {
                 let s = &self.source[leaf.span()];
                 let mut chars = s[1..s.len() - 1].chars();
+                let escape = leaf.span().start + 1..leaf.span().end - 1;
                 let c = match chars.next().unwrap() {
                     '\\' => match chars.next().unwrap() {
                         'n' => b'\n',
@@ -604,17 +2214,13 @@ impl<'a> Analyzer<'a> {
                         'r' => b'\r',
                         c @ ('\\' | '\'' | '\"') => c as u8,
                         'x' => u8::from_str_radix(&s[3..s.len() - 1], 16).unwrap_or_else(|err| {
-                            match err.kind() {
-                                IntErrorKind::PosOverflow => {
-                                    self.errors.push(Error::error(
-                                        ErrorKind::ByteOverflow,
-                                        leaf.span(),
-                                        self.source,
-                                    ));
-                                    0
-                                }
+                            let kind = match err.kind() {
+                                IntErrorKind::PosOverflow => ErrorKind::ByteOverflow,
+                                IntErrorKind::Empty => ErrorKind::InvalidEscape,
                                 _ => unreachable!(),
-                            }
+                            };
+                            self.errors.push(Error::error(kind, escape, self.source));
+                            0
                         }),
                         '0'..='7' => u8::from_str_radix(&s[2..s.len() - 1], 8).unwrap_or_else(
                             |err| match err.kind() {
@@ -629,7 +2235,14 @@ impl<'a> Analyzer<'a> {
                                 _ => unreachable!(),
                             },
                         ),
-                        _ => unreachable!(),
+                        _ => {
+                            self.errors.push(Error::error(
+                                ErrorKind::InvalidEscape,
+                                escape,
+                                self.source,
+                            ));
+                            0
+                        }
                     },
                     c => c as u8,
                 };
@@ -637,22 +2250,22 @@ impl<'a> Analyzer<'a> {
                     Some(LeafData::new(LeafKind::Value(Value {
                         value: Some(ValueData::Char(c)),
                         syntax: TreeElement::Leaf(id),
-                        type_: MaybeTyped::Typed(ValueType::Number),
+                        type_: MaybeTyped::Typed(ValueType::Char),
                     })))
                 })
             }
             String => {
                 let mut new = Vec::new();
                 let s = &self.source[leaf.span()];
-                let mut chars = s[1..s.len() - 1].chars().enumerate().peekable();
+                let mut chars = s[1..s.len() - 1].char_indices().peekable();
                 while let Some((i, c)) = chars.next() {
                     new.push(match c {
-                        '\\' => match chars.next().unwrap().1 {
-                            'n' => b'\n',
-                            't' => b'\t',
-                            'r' => b'\r',
-                            c @ ('\\' | '\'' | '\"') => c as u8,
-                            'x' => {
+                        '\\' => match chars.next().unwrap() {
+                            (_, 'n') => b'\n',
+                            (_, 't') => b'\t',
+                            (_, 'r') => b'\r',
+                            (_, c @ ('\\' | '\'' | '\"')) => c as u8,
+                            (_, 'x') => {
                                 let i = i + 3;
                                 let mut j = i;
                                 while matches!(chars.peek(), Some((_, c)) if c.is_ascii_hexdigit())
@@ -661,20 +2274,17 @@ impl<'a> Analyzer<'a> {
                                     chars.next();
                                 }
                                 u8::from_str_radix(&s[i..j], 16).unwrap_or_else(|err| {
-                                    match err.kind() {
-                                        IntErrorKind::PosOverflow => {
-                                            self.errors.push(Error::error(
-                                                ErrorKind::ByteOverflow,
-                                                leaf.span(),
-                                                self.source,
-                                            ));
-                                            0
-                                        }
+                                    let kind = match err.kind() {
+                                        IntErrorKind::PosOverflow => ErrorKind::ByteOverflow,
+                                        IntErrorKind::Empty => ErrorKind::InvalidEscape,
                                         e => unreachable!("{e:?}"),
-                                    }
+                                    };
+                                    let escape = leaf.span().start + i - 2..leaf.span().start + j;
+                                    self.errors.push(Error::error(kind, escape, self.source));
+                                    0
                                 })
                             }
-                            '0'..='7' => {
+                            (_, '0'..='7') => {
                                 let i = i + 2;
                                 let mut j = i + 1;
                                 while matches!(chars.peek(), Some((_, '0'..='7'))) {
@@ -686,7 +2296,7 @@ impl<'a> Analyzer<'a> {
                                         IntErrorKind::PosOverflow => {
                                             self.errors.push(Error::error(
                                                 ErrorKind::ByteOverflow,
-                                                leaf.span(),
+                                                leaf.span().start + i - 1..leaf.span().start + j,
                                                 self.source,
                                             ));
                                             0
@@ -695,7 +2305,16 @@ impl<'a> Analyzer<'a> {
                                     }
                                 })
                             }
-                            _ => unreachable!(),
+                            (j, c) => {
+                                let escape = leaf.span().start + i + 1
+                                    ..leaf.span().start + j + 1 + c.len_utf8();
+                                self.errors.push(Error::error(
+                                    ErrorKind::InvalidEscape,
+                                    escape,
+                                    self.source,
+                                ));
+                                0
+                            }
                         },
                         c => c as u8,
                     });
@@ -705,11 +2324,43 @@ impl<'a> Analyzer<'a> {
                     Some(LeafData::new(LeafKind::Value(Value {
                         value: Some(ValueData::String(new)),
                         syntax: TreeElement::Leaf(id),
-                        type_: MaybeTyped::Typed(ValueType::Pointer(Box::new(ValueType::Number))),
+                        type_: MaybeTyped::Typed(ValueType::String),
                     })))
                 })
             }
             Identifier => {
+                let source = self.source;
+                let name = &source[leaf.span()];
+                let constant = self
+                    .get_loc(name)
+                    .is_some_and(|loc| self.constants.contains(&loc));
+                if let Some(loc) = self.get_loc(name).filter(|_| !constant) {
+                    self.accessed.insert(loc);
+                    // functions only get the values of their variables through their parameters
+                    let outer = self.frames.last().is_some_and(|&start| loc < start);
+                    if outer && !self.params.contains_key(&loc) {
+                        let binding = self.memory[loc].syntax.get_from_builder(&self.builder);
+                        self.errors.extend([
+                            Error::error(
+                                ErrorKind::OuterVariable {
+                                    name: name.to_string(),
+                                },
+                                leaf.span(),
+                                self.source,
+                            ),
+                            Error::help(ErrorKind::DefinedOutside, binding.span(), self.source),
+                        ]);
+                    }
+                    // variables defined outside of the enclosing lambdas are captured by them
+                    for (start, captures) in self.captures.iter_mut().rev() {
+                        if loc >= *start {
+                            break;
+                        }
+                        if !captures.iter().any(|span| &source[span.clone()] == name) {
+                            captures.push(leaf.span());
+                        }
+                    }
+                }
                 let type_ = self
                     .get(&self.source[leaf.span()])
                     .and_then(|v| v.type_.type_().cloned())
@@ -728,7 +2379,11 @@ impl<'a> Analyzer<'a> {
                                 MaybeTyped::UnTyped(TreeElement::Leaf(leaf.id()))
                             }),
                         }))
-                        .assignable(AssignLHS::Ident),
+                        .assignable(if constant {
+                            AssignLHS::Invalid
+                        } else {
+                            AssignLHS::Ident
+                        }),
                     )
                 })
             }
@@ -736,3 +2391,57 @@ impl<'a> Analyzer<'a> {
         }
     }
 }
+
+/// Value of a number literal, which is decimal unless prefixed by `0x`, `0o` or `0b` and whose
+/// digits can be separated by underscores. The error is given with the span of the offending part
+/// of the literal
+#[allow(clippy::result_large_err)]
+fn number(literal: &str) -> Result<u32, (ErrorKind, Span)> {
+    let (radix, start) = match literal.get(..2) {
+        Some("0x" | "0X") => (16, 2),
+        Some("0o" | "0O") => (8, 2),
+        Some("0b" | "0B") => (2, 2),
+        _ => (10, 0),
+    };
+    if literal.len() == start {
+        return Err((ErrorKind::NoDigits, 0..literal.len()));
+    }
+    let mut digits = std::string::String::new();
+    let mut chars = literal.char_indices().skip(start).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '_' if digits.is_empty()
+                || !matches!(chars.peek(), Some((_, c)) if c.is_digit(radix)) =>
+            {
+                return Err((ErrorKind::MisplacedSeparator, i..i + 1))
+            }
+            '_' => (),
+            c if c.is_digit(radix) => digits.push(c),
+            _ => return Err((ErrorKind::InvalidDigit { radix }, i..i + 1)),
+        }
+    }
+    u32::from_str_radix(&digits, radix).map_err(|_| (ErrorKind::ByteOverflow, 0..literal.len()))
+}
+
+/// Value of a float literal, whose digits can be separated by underscores. The error is the span of
+/// a misplaced underscore
+fn float(literal: &str) -> Result<f64, Span> {
+    let bytes = literal.as_bytes();
+    for (i, &c) in bytes.iter().enumerate() {
+        if c == b'_' && !bytes[i + 1..].first().is_some_and(u8::is_ascii_digit) {
+            return Err(i..i + 1);
+        }
+    }
+    Ok(literal.replace('_', "").parse().unwrap())
+}
+
+/// Kind of the result of a function, it comes right before the body while the kind of a parameter
+/// follows a colon
+fn result_kind(tree: &ParseTree, fx: &Node<()>) -> Option<NodeId> {
+    let elements = fx.children_with_leaves(tree).collect::<Vec<_>>();
+    match elements[..] {
+        [.., TreeElement::Leaf(colon), _, _] if tree.leaf(colon).kind() == Colon => None,
+        [.., TreeElement::Node(kind), _] if tree.node(kind).kind() == Kind => Some(kind),
+        _ => None,
+    }
+}
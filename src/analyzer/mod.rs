@@ -2,11 +2,11 @@ pub mod builder;
 pub mod type_checker;
 pub mod value;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     error::Error,
-    tree::{Tree, TreeBuilder},
+    tree::{NodeId, Tree, TreeBuilder},
 };
 
 use value::{LeafData, NodeData, Value};
@@ -16,6 +16,8 @@ pub type AnalyzedTreeBuilder = TreeBuilder<NodeData, LeafData>;
 
 pub struct AnalysisResult<'a> {
     pub errors: Vec<Error<'a>>,
+    /// Problems that don't keep the program from compiling
+    pub warnings: Vec<Error<'a>>,
     pub analyzed: Analyzed<'a>,
 }
 
@@ -23,4 +25,7 @@ pub struct Analyzed<'a> {
     pub tree: AnalyzedTree,
     pub memory: Vec<Value>,
     pub lookup: Vec<HashMap<&'a str, (Vec<usize>, usize)>>,
+    /// Calls whose arguments given by name were reported as not matching the parameters, their
+    /// number isn't checked
+    pub misnamed: HashSet<NodeId>,
 }
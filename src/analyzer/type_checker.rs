@@ -1,13 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    error::{Error, ErrorKind},
+    error::{self, Error, ErrorKind},
     parser::syntax::SyntaxKind,
     tree::{Leaf, LeafId, Node, NodeId, TreeElement},
+    Span,
 };
 
 use super::{
-    value::{LeafData, LeafKind, MaybeTyped, NodeData, NodeKind, Value, ValueType, BUILT_INS},
+    value::{
+        AssignLHS, LeafData, LeafKind, MaybeTyped, NodeData, NodeKind, Value, ValueData, ValueType,
+        BUILT_INS,
+    },
     AnalysisResult, Analyzed, AnalyzedTree, AnalyzedTreeBuilder,
 };
 
@@ -17,9 +21,19 @@ use SyntaxKind::*;
 pub struct TypeChecker<'a> {
     source: &'a str,
     errors: Vec<Error<'a>>,
+    warnings: Vec<Error<'a>>,
     lookup: Vec<HashMap<&'a str, (Vec<usize>, usize)>>,
-    current_scope: usize,
+    /// Lookup maps of the enclosing scopes, innermost last
+    scopes: Vec<usize>,
+    /// Number of scopes entered so far
+    entered: usize,
     memory: Vec<Value>,
+    /// Functions declared before their definition
+    declared: HashSet<NodeId>,
+    /// Return types of the enclosing functions, none for the ones inferring it
+    returns: Vec<Option<ValueType>>,
+    /// Calls whose arguments given by name were already reported, their number isn't checked
+    misnamed: HashSet<NodeId>,
     tree: Option<AnalyzedTree>,
     builder: AnalyzedTreeBuilder,
 }
@@ -29,9 +43,14 @@ impl<'a> TypeChecker<'a> {
         TypeChecker {
             source,
             errors: result.errors,
+            warnings: result.warnings,
             lookup: result.analyzed.lookup,
-            current_scope: 0,
+            scopes: vec![0],
+            entered: 0,
             memory: result.analyzed.memory,
+            declared: HashSet::new(),
+            returns: Vec::new(),
+            misnamed: result.analyzed.misnamed,
             tree: Some(result.analyzed.tree),
             builder: AnalyzedTreeBuilder::new(),
         }
@@ -41,6 +60,7 @@ impl<'a> TypeChecker<'a> {
         let tree = &self.tree.take().unwrap();
         let root = tree.node(AnalyzedTree::ROOT);
         self.builder.start_node(root.kind(), root.span().start);
+        self.declare_functions(tree, root.children());
         for &child in root.children() {
             self.analyze_node(tree, tree.node(child));
         }
@@ -53,34 +73,422 @@ impl<'a> TypeChecker<'a> {
 
         AnalysisResult {
             errors: self.errors,
+            warnings: self.warnings,
             analyzed: Analyzed {
                 tree,
                 memory: self.memory,
                 lookup: self.lookup,
+                misnamed: HashSet::new(),
             },
         }
     }
 
     #[inline]
     fn get(&self, ident: &'a str) -> Option<&Value> {
-        self.lookup[..=self.current_scope]
+        self.get_loc(ident).map(|loc| &self.memory[loc])
+    }
+
+    fn get_loc(&self, ident: &'a str) -> Option<usize> {
+        // names are visible once defined
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|&scope| self.lookup[scope].get(ident).filter(|(_, i)| *i != 0))
+            .map(|(v, i)| v[i - 1])
+    }
+
+    /// Reports a name that isn't visible, pointing at its declaration if it comes later in one of
+    /// the enclosing scopes
+    fn undefined(&mut self, tree: &AnalyzedTree, leaf: &Leaf<LeafData>) {
+        let name = &self.source[leaf.span()];
+        let later = self
+            .scopes
             .iter()
             .rev()
-            .find_map(|map| map.get(ident))
-            .and_then(|(v, i)| (*i != 0).then(|| &self.memory[v[i - 1]]))
+            .find_map(|&scope| self.lookup[scope].get(name))
+            .and_then(|(locs, _)| locs.first());
+        match later.map(|&loc| self.memory[loc].syntax.get(tree).span()) {
+            Some(declaration) => {
+                error::retract_cycles(&mut self.errors, declaration.clone(), self.source);
+                self.errors.extend([
+                    Error::error(
+                        ErrorKind::UsedBeforeDeclaration {
+                            name: name.to_string(),
+                        },
+                        leaf.span(),
+                        self.source,
+                    ),
+                    Error::help(ErrorKind::DeclaredLater, declaration, self.source),
+                ]);
+            }
+            None => self.errors.push(Error::error(
+                ErrorKind::UndefinedReference,
+                leaf.span(),
+                self.source,
+            )),
+        }
     }
 
     #[inline]
     fn increase_shadowing(&mut self, ident: &'a str) {
-        if let Some((_, i)) = self.lookup[..=self.current_scope]
-            .iter_mut()
-            .rev()
-            .find_map(|map| map.get_mut(ident))
-        {
+        let scope = *self.scopes.last().unwrap();
+        if let Some((_, i)) = self.lookup[scope].get_mut(ident) {
             *i += 1;
         }
     }
 
+    /// Enters the next scope, in the order the analyzer entered them
+    fn enter_scope(&mut self) {
+        self.entered += 1;
+        self.scopes.push(self.entered);
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Copies the leaves of the node as they are and analyzes its child nodes.
+    /// Returns the analyzed child nodes
+    fn copy_children(&mut self, tree: &AnalyzedTree, node: &Node<NodeData>) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        for element in node.children_with_leaves(tree) {
+            match element {
+                TreeElement::Node(id) => nodes.push(self.analyze_node(tree, tree.node(id))),
+                TreeElement::Leaf(id) => {
+                    let leaf = tree.leaf(id);
+                    self.builder
+                        .push(leaf.kind(), leaf.span(), |_| leaf.data().clone());
+                }
+            }
+        }
+        nodes
+    }
+
+    /// Name and value of an analyzed field node
+    fn field(&self, id: NodeId) -> Option<(Span, TreeElement<NodeId, LeafId>)> {
+        let field = self.builder.node(id);
+        if field.kind() != Field {
+            return None;
+        }
+        let mut iter = field.children_with_leaves_builder(&self.builder);
+        let name = self.builder.leaf(iter.next()?.into_leaf()?);
+        let value = iter.next()?;
+        (name.kind() == Identifier).then(|| (name.span(), value))
+    }
+
+    /// Declares the functions defined by a list of statements so that they can be called before
+    /// their definition
+    fn declare_functions(&mut self, tree: &AnalyzedTree, statements: &[NodeId]) {
+        for &statement in statements {
+            let Some(&fx) = tree.node(statement).children().first() else {
+                continue;
+            };
+            let fx = tree.node(fx);
+            if fx.kind() == Fx {
+                self.declared.insert(fx.id());
+                self.increase_shadowing(&self.source[fx.leaves(tree)[0].span()]);
+            }
+        }
+    }
+
+    /// Checks that the value of an analyzed function body has its return type, the returns in it
+    /// are checked on their own
+    fn check_result(&mut self, element: TreeElement<NodeId, LeafId>, ret: &ValueType) {
+        let TreeElement::Node(id) = element else {
+            return self.check_type(element, ret);
+        };
+        let node = self.builder.node(id);
+        let children = node.children().clone();
+        match node.kind() {
+            Statement => match children.first() {
+                Some(&child) => self.check_result(TreeElement::Node(child), ret),
+                None => self.check_type(element, ret),
+            },
+            Scope => match children.as_slice() {
+                // a body ending with a return has no value of its own
+                [.., last, _] if self.statement_kind(*last) == ReturnKw => (),
+                [.., last] if self.statement_kind(*last) != SemiColon => {
+                    self.check_result(TreeElement::Node(*last), ret)
+                }
+                _ => self.check_type(element, ret),
+            },
+            // the branches are checked instead of the condition
+            Ternary | If if children.len() == 3 => {
+                self.check_result(TreeElement::Node(children[1]), ret);
+                self.check_result(TreeElement::Node(children[2]), ret);
+            }
+            ReturnKw => (),
+            _ => self.check_type(element, ret),
+        }
+    }
+
+    /// Kind of the syntax a statement is made of
+    fn statement_kind(&self, id: NodeId) -> SyntaxKind {
+        let mut element = TreeElement::Node(id);
+        while let TreeElement::Node(id) = element {
+            let node = self.builder.node(id);
+            match node.kind() {
+                Statement | Value => {
+                    match node.children_with_leaves_builder(&self.builder).next() {
+                        Some(child) => element = child,
+                        None => return node.kind(),
+                    }
+                }
+                kind => return kind,
+            }
+        }
+        element.get_from_builder(&self.builder).kind()
+    }
+
+    /// Reports an analyzed element that isn't of the expected type, elements without a value are
+    /// of type none
+    fn check_type(&mut self, element: TreeElement<NodeId, LeafId>, expected: &ValueType) {
+        let element = element.get_from_builder(&self.builder);
+        let found = match element {
+            TreeElement::Node(node) => node.data().as_ref().map(|data| data.type_()),
+            TreeElement::Leaf(leaf) => match leaf.data().as_deref() {
+                Some(LeafKind::Value(value)) => Some(&value.type_),
+                _ => None,
+            },
+        };
+        let found = match found {
+            Some(MaybeTyped::Typed(t)) => t.clone(),
+            Some(_) => ValueType::Poisoned,
+            None => ValueType::None,
+        };
+        if &found != expected && found != ValueType::Poisoned && expected != &ValueType::Poisoned {
+            self.errors.push(Error::error(
+                ErrorKind::WrongType {
+                    expected: expected.clone(),
+                    found,
+                },
+                element.span(),
+                self.source,
+            ));
+        }
+    }
+
+    /// Value of an analyzed element if it is a number known at compile time
+    fn constant(&self, element: TreeElement<NodeId, LeafId>) -> Option<u32> {
+        match element {
+            TreeElement::Node(id) => {
+                let node = self.builder.node(id);
+                match node.kind() {
+                    Value => {
+                        self.constant(node.children_with_leaves_builder(&self.builder).next()?)
+                    }
+                    BinaryOp => match node.data().as_deref()? {
+                        NodeKind::Value(Value {
+                            value: Some(ValueData::Number(n)),
+                            ..
+                        }) => Some(*n),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            TreeElement::Leaf(id) => match self.builder.leaf(id).data().as_deref()? {
+                LeafKind::Value(Value {
+                    value: Some(ValueData::Number(n)),
+                    ..
+                }) => Some(*n),
+                _ => None,
+            },
+        }
+    }
+
+    /// Value of an analyzed element if it is a number known at compile time, unlike the folded
+    /// constants it can be negative
+    fn signed(&self, element: TreeElement<NodeId, LeafId>) -> Option<i64> {
+        if let Some(n) = self.constant(element) {
+            return Some(n as i64);
+        }
+        let TreeElement::Node(id) = element else {
+            return None;
+        };
+        let node = self.builder.node(id);
+        let mut iter = node.children_with_leaves_builder(&self.builder);
+        match node.kind() {
+            Value => self.signed(iter.next()?),
+            UnaryOp => {
+                let op = self.builder.leaf(iter.next()?.into_leaf()?).kind();
+                let a = self.signed(iter.next()?)?;
+                (op == Sub).then(|| a.checked_neg())?
+            }
+            BinaryOp => {
+                let a = self.signed(iter.next()?)?;
+                let op = self.builder.leaf(iter.next()?.into_leaf()?).kind();
+                let b = self.signed(iter.next()?)?;
+                match op {
+                    Add => a.checked_add(b),
+                    Sub => a.checked_sub(b),
+                    Mul => a.checked_mul(b),
+                    Pow => a.checked_pow(b.try_into().ok()?),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Warns about the statements of a scope following a `return`, `break` or `continue`
+    fn unreachable(&mut self, tree: &AnalyzedTree, statements: &[NodeId]) {
+        let jump = statements.iter().position(|&id| {
+            let statement = tree.node(id);
+            statement
+                .children()
+                .iter()
+                .any(|&child| matches!(tree.node(child).kind(), ReturnKw | BreakKw | ContinueKw))
+        });
+        let rest = match jump {
+            Some(i) => &statements[i + 1..],
+            None => return,
+        };
+        // a lone `;` ends the statement before it
+        let rest = rest
+            .iter()
+            .map(|&id| tree.node(id))
+            .filter(|statement| {
+                !statement.children().iter().all(|&child| {
+                    let value = tree.node(child);
+                    value.kind() == Value
+                        && value.children().is_empty()
+                        && value.leaves(tree).iter().all(|l| l.kind() == SemiColon)
+                })
+            })
+            .collect::<Vec<_>>();
+        if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+            self.warnings.push(Error::warning(
+                ErrorKind::UnreachableCode,
+                first.span().start..last.span().end,
+                self.source,
+            ));
+        }
+    }
+
+    /// Value of an analyzed element if it is a boolean known at compile time
+    fn truth(&self, element: TreeElement<NodeId, LeafId>) -> Option<bool> {
+        match element {
+            TreeElement::Node(id) => {
+                let node = self.builder.node(id);
+                match node.kind() {
+                    Value => self.truth(node.children_with_leaves_builder(&self.builder).next()?),
+                    BinaryOp | UnaryOp => match node.data().as_deref()? {
+                        NodeKind::Value(Value {
+                            value: Some(ValueData::Bool(b)),
+                            ..
+                        }) => Some(*b),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            TreeElement::Leaf(id) => match self.builder.leaf(id).data().as_deref()? {
+                LeafKind::Value(Value {
+                    value: Some(ValueData::Bool(b)),
+                    ..
+                }) => Some(*b),
+                _ => None,
+            },
+        }
+    }
+
+    /// Span of the part of an analyzed element that keeps it from being known at compile time
+    fn non_constant(&self, element: TreeElement<NodeId, LeafId>) -> Span {
+        if let TreeElement::Node(id) = element {
+            let node = self.builder.node(id);
+            let mut iter = node.children_with_leaves_builder(&self.builder);
+            match node.kind() {
+                Value => return self.non_constant(iter.next().unwrap()),
+                BinaryOp => {
+                    let a = iter.next().unwrap();
+                    let b = iter.nth(1).unwrap();
+                    for e in [a, b] {
+                        if self.constant(e).is_none() && self.truth(e).is_none() {
+                            return self.non_constant(e);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        element.get_from_builder(&self.builder).span()
+    }
+
+    /// Checks the pattern of a match arm against the matched value and binds its names.
+    /// Returns the variant matched by enum patterns
+    fn pattern(
+        &mut self,
+        tree: &AnalyzedTree,
+        node: &Node<NodeData>,
+        scrutinee: &ValueType,
+    ) -> Option<&'a str> {
+        self.builder.start_node(node.kind(), node.span().start);
+        for leaf in node.leaves(tree) {
+            self.builder
+                .push(leaf.kind(), leaf.span(), |_| leaf.data().clone());
+        }
+        // bindings take the type of the matched value
+        let type_ = match node.data().as_ref().unwrap().type_() {
+            MaybeTyped::Typed(t) => t.clone(),
+            _ => scrutinee.clone(),
+        };
+        self.builder.finish_node(node.span().end, |id| {
+            Some(NodeData::new(NodeKind::Value(Value {
+                value: None,
+                syntax: TreeElement::Node(id),
+                type_: MaybeTyped::Typed(type_.clone()),
+            })))
+        });
+        let type_ = &type_;
+        if type_ != scrutinee && ![type_, scrutinee].contains(&&ValueType::Poisoned) {
+            self.errors.push(Error::error(
+                ErrorKind::WrongType {
+                    expected: scrutinee.clone(),
+                    found: type_.clone(),
+                },
+                node.span(),
+                self.source,
+            ));
+        }
+        match node.leaves(tree) {
+            [number] if number.kind() == Number => None,
+            [binding] => {
+                self.increase_shadowing(&self.source[binding.span()]);
+                None
+            }
+            [_, variant, bindings @ ..] => {
+                for binding in bindings {
+                    self.increase_shadowing(&self.source[binding.span()]);
+                }
+                let ValueType::Enum(_, variants) = type_ else {
+                    return None;
+                };
+                let name = &self.source[variant.span()];
+                match variants.iter().find(|(v, _)| v == name) {
+                    None => self.errors.push(Error::error(
+                        ErrorKind::UndefinedReference,
+                        variant.span(),
+                        self.source,
+                    )),
+                    Some((_, payload)) if payload.len() != bindings.len() => {
+                        self.errors.push(Error::error(
+                            ErrorKind::TooManyArgs {
+                                expected: payload.len(),
+                                found: bindings.len(),
+                            },
+                            node.span(),
+                            self.source,
+                        ))
+                    }
+                    Some(_) => return Some(name),
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
     fn analyze_element(
         &mut self,
         tree: &AnalyzedTree,
@@ -103,11 +511,13 @@ impl<'a> TypeChecker<'a> {
             }
             Scope => {
                 self.builder.start_node(node.kind(), node.span().start);
-                self.current_scope += 1;
+                self.enter_scope();
+                self.declare_functions(tree, node.children());
                 for &child in node.children() {
                     self.analyze_node(tree, tree.node(child));
                 }
-                self.current_scope -= 1;
+                self.unreachable(tree, node.children());
+                self.exit_scope();
                 self.builder.finish_node(node.span().end, |_| None)
             }
             BinaryOp => {
@@ -118,8 +528,36 @@ impl<'a> TypeChecker<'a> {
                 let b = iter.next().unwrap();
                 let a = self.analyze_element(tree, a).into_node().unwrap();
                 let op = self.analyze_element(tree, op).into_leaf().unwrap();
+                let op_span = self.builder.leaf(op).span();
                 let op = self.builder.leaf(op).kind();
                 let b = self.analyze_element(tree, b).into_node().unwrap();
+                let (value_a, value_b) = (
+                    self.constant(TreeElement::Node(a)),
+                    self.constant(TreeElement::Node(b)),
+                );
+                if matches!(op, Div | Mod) && value_b == Some(0) {
+                    self.errors.push(Error::error(
+                        ErrorKind::DivisionByZero,
+                        op_span.clone(),
+                        self.source,
+                    ));
+                }
+                // operations on constants are folded to check the ones using them
+                let value = match value_a.zip(value_b) {
+                    Some((a, b)) => fold(op, a, b)
+                        .map(ValueData::Number)
+                        .or_else(|| compare(op, a, b).map(ValueData::Bool)),
+                    None => self
+                        .truth(TreeElement::Node(a))
+                        .zip(self.truth(TreeElement::Node(b)))
+                        .and_then(|(a, b)| logic(op, a, b))
+                        .map(ValueData::Bool),
+                };
+                let overflow = value.is_none() && matches!(op, Add | Sub | Mul | Pow);
+                let wide = (
+                    self.signed(TreeElement::Node(a)),
+                    self.signed(TreeElement::Node(b)),
+                );
                 let a = self.builder.node(a);
                 let b = self.builder.node(b);
                 let type_a = a.data().as_ref().unwrap().type_();
@@ -130,7 +568,7 @@ impl<'a> TypeChecker<'a> {
                         None => {
                             self.errors.push(Error::error(
                                 ErrorKind::UnsupportedOperation { operation: op },
-                                node.span(),
+                                op_span.clone(),
                                 self.source,
                             ));
                             ValueType::Poisoned
@@ -139,13 +577,26 @@ impl<'a> TypeChecker<'a> {
                 } else {
                     unreachable!()
                 };
-                self.builder.finish_node(node.span().end, |id| {
+                let id = self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
-                        value: None,
+                        value,
                         syntax: TreeElement::Node(id),
                         type_,
                     })))
-                })
+                });
+                // the folded constants are narrower than the numbers they are computed with
+                if overflow
+                    && wide.0.is_some()
+                    && wide.1.is_some_and(|b| b >= 0 || op != Pow)
+                    && self.signed(TreeElement::Node(id)).is_none()
+                {
+                    self.errors.push(Error::error(
+                        ErrorKind::ConstantOverflow,
+                        op_span,
+                        self.source,
+                    ));
+                }
+                id
             }
             UnaryOp => {
                 self.builder.start_node(node.kind(), node.span().start);
@@ -156,11 +607,8 @@ impl<'a> TypeChecker<'a> {
                     .analyze_element(tree, op)
                     .get_from_builder(&self.builder)
                     .kind();
-                let a = self
-                    .analyze_element(tree, a)
-                    .into_node()
-                    .unwrap()
-                    .get_from_builder(&self.builder);
+                let e_a = self.analyze_element(tree, a);
+                let a = e_a.into_node().unwrap().get_from_builder(&self.builder);
                 let type_a = a.data().as_ref().unwrap().type_();
                 let type_ = if let Some(a) = type_a.type_() {
                     MaybeTyped::Typed(match a.operate_unary(op) {
@@ -177,30 +625,49 @@ impl<'a> TypeChecker<'a> {
                 } else {
                     unreachable!()
                 };
+                let value = match op {
+                    SyntaxKind::Not => self.truth(e_a).map(|b| ValueData::Bool(!b)),
+                    _ => None,
+                };
                 self.builder.finish_node(node.span().end, |id| {
-                    Some(NodeData::new(NodeKind::Value(Value {
-                        value: None,
-                        syntax: TreeElement::Node(id),
-                        type_,
-                    })))
+                    Some(
+                        NodeData::new(NodeKind::Value(Value {
+                            value,
+                            syntax: TreeElement::Node(id),
+                            type_,
+                        }))
+                        .assignable(if op == SyntaxKind::Mul {
+                            AssignLHS::Deref(e_a)
+                        } else {
+                            AssignLHS::Invalid
+                        }),
+                    )
                 })
             }
             Cast => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
-                let a = iter.next().unwrap();
-                let b = iter.next().unwrap();
-                let type_ = self
-                    .analyze_element(tree, a)
-                    .into_node()
-                    .unwrap()
-                    .get_from_builder(&self.builder)
-                    .data()
-                    .as_ref()
-                    .unwrap()
-                    .type_()
-                    .clone();
-                self.analyze_element(tree, b);
+                let mut type_ = MaybeTyped::Typed(ValueType::Poisoned);
+                let mut from = MaybeTyped::Typed(ValueType::Poisoned);
+                for element in node.children_with_leaves(tree) {
+                    let element = self.analyze_element(tree, element);
+                    let element = element.get_from_builder(&self.builder);
+                    match element.kind() {
+                        Kind => type_ = element.type_().clone(),
+                        _ => from = element.type_().clone(),
+                    }
+                }
+                if let (Some(from), Some(to)) = (from.type_(), type_.type_()) {
+                    if !from.can_cast(to) {
+                        self.errors.push(Error::error(
+                            ErrorKind::InvalidCast {
+                                from: from.clone(),
+                                to: to.clone(),
+                            },
+                            node.span(),
+                            self.source,
+                        ));
+                    }
+                }
                 self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
                         value: None,
@@ -211,11 +678,108 @@ impl<'a> TypeChecker<'a> {
             }
             Let => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
-                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
-                self.builder.push(ident.kind(), ident.span(), |_| None);
-                self.analyze_element(tree, iter.next().unwrap());
-                self.increase_shadowing(&self.source[ident.span()]);
+                let mut iter = node.children_with_leaves(tree).peekable();
+                if let Some(mutable) = iter.next_if(|e| e.get(tree).kind() == MutKw) {
+                    let mutable = mutable.get(tree);
+                    self.builder.push(mutable.kind(), mutable.span(), |_| None);
+                }
+                let target = iter.next().unwrap();
+                let (span, leaves) = match target {
+                    TreeElement::Leaf(ident) => {
+                        let ident = tree.leaf(ident);
+                        self.builder.push(ident.kind(), ident.span(), |_| None);
+                        (ident.span(), vec![ident])
+                    }
+                    TreeElement::Node(pattern) => {
+                        let pattern = tree.node(pattern);
+                        self.builder
+                            .start_node(pattern.kind(), pattern.span().start);
+                        for leaf in pattern.leaves(tree) {
+                            self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                        }
+                        self.builder.finish_node(pattern.span().end, |_| None);
+                        (pattern.span(), pattern.leaves(tree).iter().collect())
+                    }
+                };
+                let rest = iter.collect::<Vec<_>>();
+                let (&value, kind) = rest.split_last().unwrap();
+                let kind = kind.first().map(|&kind| {
+                    self.analyze_element(tree, kind)
+                        .get_from_builder(&self.builder)
+                        .type_()
+                        .clone()
+                });
+                let value = self
+                    .analyze_element(tree, value)
+                    .get_from_builder(&self.builder);
+                let found = value.type_().type_().unwrap().clone();
+                let type_ = match kind {
+                    Some(MaybeTyped::Typed(kind)) => {
+                        if !found.fits(&kind) && ![&kind, &found].contains(&&ValueType::Poisoned) {
+                            self.errors.push(Error::error(
+                                ErrorKind::WrongType {
+                                    expected: kind.clone(),
+                                    found,
+                                },
+                                span.clone(),
+                                self.source,
+                            ));
+                        }
+                        kind
+                    }
+                    _ => found,
+                };
+                let bindings = match leaves.as_slice() {
+                    [open, bindings @ ..] if open.kind() == OpenParen => {
+                        match &type_ {
+                            ValueType::Tuple(types) if types.len() == bindings.len() => (),
+                            ValueType::Poisoned => (),
+                            _ => self.errors.push(Error::error(
+                                ErrorKind::TypeError {
+                                    type_: type_.clone(),
+                                },
+                                span,
+                                self.source,
+                            )),
+                        }
+                        bindings
+                    }
+                    [name, _, bindings @ ..] => {
+                        match &type_ {
+                            ValueType::Struct(struct_, _)
+                                if struct_ == &self.source[name.span()] =>
+                            {
+                                for binding in bindings {
+                                    if type_.field(&self.source[binding.span()]).is_none() {
+                                        self.errors.push(Error::error(
+                                            ErrorKind::UndefinedReference,
+                                            binding.span(),
+                                            self.source,
+                                        ));
+                                    }
+                                }
+                            }
+                            ValueType::Poisoned => (),
+                            _ => self.errors.push(Error::error(
+                                ErrorKind::TypeError {
+                                    type_: type_.clone(),
+                                },
+                                span,
+                                self.source,
+                            )),
+                        }
+                        bindings
+                    }
+                    bindings => bindings,
+                };
+                for binding in bindings {
+                    self.increase_shadowing(&self.source[binding.span()]);
+                }
+                // calls of functions defined later are only typed now that their body is analyzed
+                if let TreeElement::Leaf(ident) = target {
+                    let loc = self.get_loc(&self.source[tree.leaf(ident).span()]).unwrap();
+                    self.memory[loc].type_ = MaybeTyped::Typed(type_);
+                }
                 self.builder.finish_node(node.span().end, |_| None)
             }
             ReLet => {
@@ -229,10 +793,18 @@ impl<'a> TypeChecker<'a> {
                     .analyze_element(tree, op)
                     .into_leaf()
                     .unwrap()
-                    .get_from_builder(&self.builder)
-                    .kind()
-                    .op_assignment();
+                    .get_from_builder(&self.builder);
+                let op_span = op.span();
+                let op = op.kind().op_assignment();
                 let rhs = self.analyze_element(tree, rhs).into_node().unwrap();
+                if matches!(op, Some(Div | Mod)) && self.constant(TreeElement::Node(rhs)) == Some(0)
+                {
+                    self.errors.push(Error::error(
+                        ErrorKind::DivisionByZero,
+                        op_span,
+                        self.source,
+                    ));
+                }
                 let lhs = lhs.get_from_builder(&self.builder);
                 let rhs = rhs.get_from_builder(&self.builder);
                 let data_lhs = lhs.data().as_ref().unwrap();
@@ -251,7 +823,7 @@ impl<'a> TypeChecker<'a> {
                             }
                         }
                         None => {
-                            if a != b && ![a, b].contains(&&ValueType::Poisoned) {
+                            if !b.fits(a) && ![a, b].contains(&&ValueType::Poisoned) {
                                 self.errors.push(Error::error(
                                     ErrorKind::TypeError { type_: b.clone() },
                                     rhs.span(),
@@ -279,9 +851,395 @@ impl<'a> TypeChecker<'a> {
                     })))
                 })
             }
-            Loop => {
+            Tuple => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let types = node
+                    .children()
+                    .iter()
+                    .map(|&child| {
+                        let element = self.analyze_node(tree, tree.node(child));
+                        let element = self.builder.node(element).data().as_ref().unwrap();
+                        element.type_().type_().unwrap().clone()
+                    })
+                    .collect();
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(ValueType::Tuple(types)),
+                    })))
+                })
+            }
+            List => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut type_ = None;
+                for &child in node.children() {
+                    let element = self.analyze_node(tree, tree.node(child));
+                    let element = self.builder.node(element);
+                    let t = element.data().as_ref().unwrap().type_().type_().unwrap();
+                    match &type_ {
+                        None => type_ = Some(t.clone()),
+                        Some(a) if a != t && ![a, t].contains(&&ValueType::Poisoned) => {
+                            self.errors.push(Error::error(
+                                ErrorKind::TypeError { type_: t.clone() },
+                                element.span(),
+                                self.source,
+                            ))
+                        }
+                        _ => (),
+                    }
+                }
+                let type_ = ValueType::Array(
+                    Box::new(type_.unwrap_or(ValueType::None)),
+                    node.children().len(),
+                );
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(type_),
+                    })))
+                })
+            }
+            Struct | Field | Enum | Variant | TypeAlias | FileKw | Capture => {
+                self.builder.start_node(node.kind(), node.span().start);
+                self.copy_children(tree, node);
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            StructLit => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let fields = self.copy_children(tree, node);
+                let type_ = node.data().as_ref().unwrap().type_().clone();
+                if let MaybeTyped::Typed(ValueType::Struct(_, defined)) = &type_ {
+                    let mut found = Vec::new();
+                    for field in fields {
+                        let Some((span, value)) = self.field(field) else {
+                            continue;
+                        };
+                        let name = &self.source[span.clone()];
+                        let Some((_, expected)) = defined.iter().find(|(f, _)| f == name) else {
+                            self.errors.push(Error::error(
+                                ErrorKind::UndefinedReference,
+                                span,
+                                self.source,
+                            ));
+                            continue;
+                        };
+                        if found.contains(&name) {
+                            self.errors.push(Error::error(
+                                ErrorKind::AlreadyDefined,
+                                span,
+                                self.source,
+                            ));
+                            continue;
+                        }
+                        found.push(name);
+                        let value = value.get_from_builder(&self.builder);
+                        let value_t = value.type_().type_().unwrap();
+                        if value_t != expected
+                            && ![value_t, expected].contains(&&ValueType::Poisoned)
+                        {
+                            self.errors.push(Error::error(
+                                ErrorKind::WrongType {
+                                    expected: expected.clone(),
+                                    found: value_t.clone(),
+                                },
+                                value.span(),
+                                self.source,
+                            ));
+                        }
+                    }
+                    for (field, _) in defined {
+                        if !found.contains(&field.as_str()) {
+                            self.errors.push(Error::error(
+                                ErrorKind::MissingField {
+                                    field: field.clone(),
+                                },
+                                node.span(),
+                                self.source,
+                            ));
+                        }
+                    }
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_,
+                    })))
+                })
+            }
+            EnumLit => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let args = self.copy_children(tree, node);
+                let type_ = node.data().as_ref().unwrap().type_().clone();
+                let variant = node.leaves(tree).get(1);
+                if let (MaybeTyped::Typed(ValueType::Enum(_, variants)), Some(variant)) =
+                    (&type_, variant)
+                {
+                    let span = variant.span();
+                    let name = &self.source[span.clone()];
+                    match variants.iter().find(|(v, _)| v == name) {
+                        None => self.errors.push(Error::error(
+                            ErrorKind::UndefinedReference,
+                            span,
+                            self.source,
+                        )),
+                        Some((_, payload)) if payload.len() != args.len() => {
+                            self.errors.push(Error::error(
+                                ErrorKind::TooManyArgs {
+                                    expected: payload.len(),
+                                    found: args.len(),
+                                },
+                                node.span(),
+                                self.source,
+                            ))
+                        }
+                        Some((_, payload)) => {
+                            for (&arg, expected) in args.iter().zip(payload) {
+                                let arg = self.builder.node(arg);
+                                let found = arg.data().as_ref().unwrap().type_();
+                                let found = found.type_().unwrap();
+                                if found != expected
+                                    && ![found, expected].contains(&&ValueType::Poisoned)
+                                {
+                                    self.errors.push(Error::error(
+                                        ErrorKind::WrongType {
+                                            expected: expected.clone(),
+                                            found: found.clone(),
+                                        },
+                                        arg.span(),
+                                        self.source,
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_,
+                    })))
+                })
+            }
+            Match => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let scrutinee = self.analyze_element(tree, iter.next().unwrap());
+                let scrutinee = scrutinee.get_from_builder(&self.builder);
+                let scrutinee = scrutinee.type_().type_().unwrap().clone();
+                let mut type_: Option<ValueType> = None;
+                let mut covered = Vec::new();
+                let mut numbers = Vec::new();
+                let mut catch_all = false;
+                for arm in iter {
+                    let arm = arm.into_node().unwrap().get(tree);
+                    self.builder.start_node(arm.kind(), arm.span().start);
+                    let mut iter = arm.children_with_leaves(tree);
+                    let pattern = iter.next().unwrap().into_node().unwrap().get(tree);
+                    self.enter_scope();
+                    let number = match pattern.leaves(tree) {
+                        [number] => match number.data().as_deref() {
+                            Some(LeafKind::Value(Value {
+                                value: Some(ValueData::Number(n)),
+                                ..
+                            })) => Some(*n),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    let variant = self.pattern(tree, pattern, &scrutinee);
+                    // an arm matching the same value as one before it is never taken
+                    let repeated = match (variant, number) {
+                        (Some(variant), _) => covered.contains(&variant),
+                        (_, Some(number)) => numbers.contains(&number),
+                        _ => false,
+                    };
+                    if catch_all {
+                        // an arm after one matching every value is left over rather than wrong
+                        self.warnings.push(Error::warning(
+                            ErrorKind::ArmAfterCatchAll,
+                            pattern.span(),
+                            self.source,
+                        ));
+                    } else if repeated {
+                        self.errors.push(Error::error(
+                            ErrorKind::RepeatedArm,
+                            pattern.span(),
+                            self.source,
+                        ));
+                    }
+                    numbers.extend(number);
+                    match variant {
+                        Some(variant) => covered.push(variant),
+                        None => {
+                            catch_all |= pattern.leaves(tree).len() == 1
+                                && pattern.leaves(tree)[0].kind() == Identifier
+                        }
+                    }
+                    if let Some(value) = iter.next() {
+                        let value = self.analyze_element(tree, value);
+                        let value = value.get_from_builder(&self.builder);
+                        let found = value.type_().type_().unwrap();
+                        match &type_ {
+                            None => type_ = Some(found.clone()),
+                            Some(expected)
+                                if expected != found
+                                    && ![expected, found].contains(&&ValueType::Poisoned) =>
+                            {
+                                self.errors.push(Error::error(
+                                    ErrorKind::WrongType {
+                                        expected: expected.clone(),
+                                        found: found.clone(),
+                                    },
+                                    value.span(),
+                                    self.source,
+                                ));
+                            }
+                            Some(_) => (),
+                        }
+                    }
+                    self.exit_scope();
+                    self.builder.finish_node(arm.span().end, |_| None);
+                }
+                let exhaustive = catch_all
+                    || match &scrutinee {
+                        ValueType::Enum(_, variants) => {
+                            variants.iter().all(|(v, _)| covered.contains(&v.as_str()))
+                        }
+                        ValueType::Poisoned => true,
+                        _ => false,
+                    };
+                if !exhaustive {
+                    self.errors.push(Error::error(
+                        ErrorKind::NonExhaustiveMatch,
+                        node.span(),
+                        self.source,
+                    ));
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(type_.unwrap_or(ValueType::None)),
+                    })))
+                })
+            }
+            Index => {
                 self.builder.start_node(node.kind(), node.span().start);
                 let mut iter = node.children_with_leaves(tree);
+                let object = self
+                    .analyze_element(tree, iter.next().unwrap())
+                    .get_from_builder(&self.builder);
+                let object_t = object.type_().type_().unwrap();
+                let type_ = object_t.index().unwrap_or_else(|| {
+                    self.errors.push(Error::error(
+                        ErrorKind::TypeError {
+                            type_: object_t.clone(),
+                        },
+                        object.span(),
+                        self.source,
+                    ));
+                    ValueType::Poisoned
+                });
+                let len = match object_t {
+                    ValueType::Array(_, len) => Some(*len),
+                    _ => None,
+                };
+                let index = self.analyze_element(tree, iter.next().unwrap());
+                if let (Some(len), Some(i)) = (len, self.signed(index)) {
+                    if i < 0 || i as usize >= len {
+                        self.errors.push(Error::error(
+                            ErrorKind::OutOfBounds { index: i, len },
+                            index.get_from_builder(&self.builder).span(),
+                            self.source,
+                        ));
+                    }
+                }
+                let index = index.get_from_builder(&self.builder);
+                let index_t = index.type_().type_().unwrap();
+                if ![ValueType::Number, ValueType::Poisoned].contains(index_t) {
+                    self.errors.push(Error::error(
+                        ErrorKind::WrongType {
+                            expected: ValueType::Number,
+                            found: index_t.clone(),
+                        },
+                        index.span(),
+                        self.source,
+                    ));
+                }
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(
+                        NodeData::new(NodeKind::Value(Value {
+                            value: None,
+                            syntax: TreeElement::Node(id),
+                            type_: MaybeTyped::Typed(type_),
+                        }))
+                        .assignable(AssignLHS::Index(TreeElement::Node(id))),
+                    )
+                })
+            }
+            Access => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let object = self
+                    .analyze_element(tree, iter.next().unwrap())
+                    .get_from_builder(&self.builder);
+                let object_t = object.type_().type_().unwrap().clone();
+                let object_span = object.span();
+                let type_ = match iter.next() {
+                    Some(field) => {
+                        let field = field.into_leaf().unwrap().get(tree);
+                        self.builder.push(field.kind(), field.span(), |_| None);
+                        let name = &self.source[field.span()];
+                        match object_t.field(name) {
+                            Some(t) => t,
+                            None if matches!(
+                                object_t,
+                                ValueType::Struct(..) | ValueType::Tuple(_)
+                            ) =>
+                            {
+                                self.errors.push(Error::error(
+                                    ErrorKind::UndefinedReference,
+                                    field.span(),
+                                    self.source,
+                                ));
+                                ValueType::Poisoned
+                            }
+                            None => {
+                                self.errors.push(Error::error(
+                                    ErrorKind::TypeError { type_: object_t },
+                                    object_span,
+                                    self.source,
+                                ));
+                                ValueType::Poisoned
+                            }
+                        }
+                    }
+                    None => ValueType::Poisoned,
+                };
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(
+                        NodeData::new(NodeKind::Value(Value {
+                            value: None,
+                            syntax: TreeElement::Node(id),
+                            type_: MaybeTyped::Typed(type_),
+                        }))
+                        .assignable(AssignLHS::Field(TreeElement::Node(id))),
+                    )
+                })
+            }
+            Loop => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree).peekable();
+                if let Some(&TreeElement::Node(label)) = iter.peek() {
+                    if tree.node(label).kind() == Label {
+                        self.analyze_node(tree, tree.node(label));
+                        iter.next();
+                    }
+                }
                 let a = iter.next().unwrap();
                 let b = iter.next().unwrap();
                 let c = iter.next().unwrap();
@@ -303,24 +1261,271 @@ impl<'a> TypeChecker<'a> {
                 self.analyze_element(tree, d);
                 self.builder.finish_node(node.span().end, |_| None)
             }
+            While => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let (&b, children) = node.children().split_last().unwrap();
+                let (&a, label) = children.split_last().unwrap();
+                for &label in label {
+                    self.analyze_node(tree, tree.node(label));
+                }
+                let cond = self.analyze_node(tree, tree.node(a));
+                let cond = cond.get_from_builder(&self.builder);
+                if let Some(a) = cond.data().as_ref().unwrap().type_().type_() {
+                    if !a.can_be_bool() {
+                        self.errors.push(Error::error(
+                            ErrorKind::TypeError { type_: a.clone() },
+                            cond.span(),
+                            self.source,
+                        ))
+                    }
+                }
+                self.analyze_node(tree, tree.node(b));
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            For => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree).peekable();
+                if let Some(&TreeElement::Node(label)) = iter.peek() {
+                    if tree.node(label).kind() == Label {
+                        self.analyze_node(tree, tree.node(label));
+                        iter.next();
+                    }
+                }
+                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                self.builder.push(ident.kind(), ident.span(), |_| None);
+                for bound in [iter.next().unwrap(), iter.next().unwrap()] {
+                    let bound = self.analyze_element(tree, bound);
+                    let bound = bound.get_from_builder(&self.builder);
+                    if let Some(a) = bound.type_().type_() {
+                        if ![ValueType::Number, ValueType::Poisoned].contains(a) {
+                            self.errors.push(Error::error(
+                                ErrorKind::WrongType {
+                                    expected: ValueType::Number,
+                                    found: a.clone(),
+                                },
+                                bound.span(),
+                                self.source,
+                            ))
+                        }
+                    }
+                }
+                self.enter_scope();
+                self.increase_shadowing(&self.source[ident.span()]);
+                self.analyze_element(tree, iter.next().unwrap());
+                self.exit_scope();
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            Const => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let mut iter = node.children_with_leaves(tree);
+                let ident = iter.next().unwrap().into_leaf().unwrap().get(tree);
+                self.builder.push(ident.kind(), ident.span(), |_| None);
+                let rest = iter.collect::<Vec<_>>();
+                let (&value, kind) = rest.split_last().unwrap();
+                let kind = kind.first().map(|&kind| self.analyze_element(tree, kind));
+                let boolean = |this: &Self, element: TreeElement<NodeId, LeafId>| {
+                    element.get_from_builder(&this.builder).type_()
+                        == &MaybeTyped::Typed(ValueType::Boolean)
+                };
+                if let Some(kind) = kind.filter(|&kind| !boolean(self, kind)) {
+                    self.check_type(kind, &ValueType::Number);
+                }
+                let errors = self.errors.len();
+                let value = self.analyze_element(tree, value);
+                // constants are numbers unless their kind or value says they are booleans
+                let constant = if boolean(self, kind.unwrap_or(value)) {
+                    self.check_type(value, &ValueType::Boolean);
+                    self.truth(value).map(ValueData::Bool)
+                } else {
+                    self.check_type(value, &ValueType::Number);
+                    self.constant(value).map(ValueData::Number)
+                };
+                // an invalid value was already reported
+                if constant.is_none() && self.errors.len() == errors {
+                    self.errors.push(Error::error(
+                        ErrorKind::NotConstant,
+                        self.non_constant(value),
+                        self.source,
+                    ));
+                }
+                let name = &self.source[ident.span()];
+                self.increase_shadowing(name);
+                // the value is inlined where the constant is used
+                let loc = self.get_loc(name).unwrap();
+                self.memory[loc].value = constant;
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            ReturnKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for &child in node.children() {
+                    let value = self.analyze_node(tree, tree.node(child));
+                    if let Some(Some(ret)) = self.returns.last().cloned() {
+                        self.check_type(TreeElement::Node(value), &ret);
+                    }
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            AssertKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                let element = node.children_with_leaves(tree).next().unwrap();
+                let element = self.analyze_element(tree, element);
+                let cond = element.get_from_builder(&self.builder);
+                if let Some(a) = cond.type_().type_() {
+                    if !matches!(a, ValueType::Boolean | ValueType::Poisoned) {
+                        self.errors.push(Error::error(
+                            ErrorKind::TypeError { type_: a.clone() },
+                            cond.span(),
+                            self.source,
+                        ))
+                    }
+                }
+                if self.truth(element) == Some(false) {
+                    self.errors.push(Error::error(
+                        ErrorKind::AssertionFails,
+                        node.span(),
+                        self.source,
+                    ));
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            Label => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for leaf in node.leaves(tree) {
+                    self.builder.push(leaf.kind(), leaf.span(), |_| None);
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
+            BreakKw | ContinueKw => {
+                self.builder.start_node(node.kind(), node.span().start);
+                for element in node.children_with_leaves(tree) {
+                    self.analyze_element(tree, element);
+                }
+                self.builder.finish_node(node.span().end, |_| None)
+            }
             Fx => {
                 self.builder.start_node(node.kind(), node.span().start);
-                self.lookup.push(HashMap::new());
-                self.current_scope += 1;
                 let mut iter = node.children_with_leaves(tree);
                 let name = iter.next().unwrap().into_leaf().unwrap().get(tree);
                 self.builder.push(name.kind(), name.span(), |_| None);
-                let mut args = iter.collect::<Vec<_>>();
-                let body = args.pop().unwrap();
-                for arg in args {
-                    let arg = arg.into_leaf().unwrap().get(tree);
-                    self.builder.push(arg.kind(), arg.span(), |_| None);
-                    self.increase_shadowing(&self.source[arg.span()]);
-                }
-                self.analyze_element(tree, body);
-                self.current_scope -= 1;
+                if !self.declared.remove(&node.id()) {
+                    self.increase_shadowing(&self.source[name.span()]);
+                }
+                let mut params = iter.collect::<Vec<_>>();
+                let body = params.pop().unwrap();
+                // the kind of the last parameter follows a colon
+                let kind = match &params[..] {
+                    [.., colon, _] if colon.get(tree).kind() == Colon => None,
+                    [.., kind] if kind.get(tree).kind() == Kind => params.pop(),
+                    _ => None,
+                };
+                // functions without a kind return the type inferred from their body
+                let mut types = match &self.get(&self.source[name.span()]).unwrap().type_ {
+                    MaybeTyped::Typed(ValueType::FnPtr(types)) => types.clone(),
+                    _ => vec![ValueType::Poisoned],
+                };
+                let inferred = types.pop().unwrap();
+                let mut types = types.into_iter();
+                self.enter_scope();
+                let mut params = params.into_iter().peekable();
+                while let Some(param) = params.next() {
+                    let param = param.into_leaf().unwrap().get(tree);
+                    self.builder.push(param.kind(), param.span(), |_| None);
+                    if param.kind() == Ellipsis {
+                        continue;
+                    }
+                    if let Some(colon) = params.next_if(|p| p.get(tree).kind() == Colon) {
+                        let colon = colon.get(tree);
+                        self.builder.push(colon.kind(), colon.span(), |_| None);
+                        self.analyze_element(tree, params.next().unwrap());
+                    }
+                    let expected = match types.next() {
+                        Some(ValueType::Default(type_)) => *type_,
+                        _ => ValueType::Number,
+                    };
+                    // the default value is checked before its parameter is defined
+                    if let Some(assign) = params.next_if(|p| p.get(tree).kind() == Assign) {
+                        let assign = assign.get(tree);
+                        self.builder.push(assign.kind(), assign.span(), |_| None);
+                        let default = self
+                            .analyze_element(tree, params.next().unwrap())
+                            .get_from_builder(&self.builder);
+                        let type_ = default.type_().type_().unwrap();
+                        if !type_.fits(&expected) && *type_ != ValueType::Poisoned {
+                            self.errors.push(Error::error(
+                                ErrorKind::WrongType {
+                                    expected,
+                                    found: type_.clone(),
+                                },
+                                default.span(),
+                                self.source,
+                            ));
+                        }
+                    }
+                    self.increase_shadowing(&self.source[param.span()]);
+                }
+                let ret = match kind {
+                    Some(kind) => {
+                        let kind = self.analyze_element(tree, kind);
+                        let kind = kind.get_from_builder(&self.builder).type_();
+                        kind.type_().cloned().unwrap_or(ValueType::Poisoned)
+                    }
+                    None => inferred,
+                };
+                self.returns.push(Some(ret.clone()));
+                let body = self.analyze_element(tree, body);
+                self.returns.pop();
+                self.check_result(body, &ret);
+                self.exit_scope();
                 self.builder.finish_node(node.span().end, |_| None)
             }
+            Lambda => {
+                self.builder.start_node(node.kind(), node.span().start);
+                self.enter_scope();
+                let mut params = node.children_with_leaves(tree).collect::<Vec<_>>();
+                let captures = params.pop().unwrap();
+                let body = params.pop().unwrap();
+                let mut types = Vec::new();
+                for param in params {
+                    let param = tree.node(param.into_node().unwrap());
+                    self.builder.start_node(param.kind(), param.span().start);
+                    self.copy_children(tree, param);
+                    self.builder.finish_node(param.span().end, |_| None);
+                    types.push(match param.children().first() {
+                        Some(&kind) => tree
+                            .node(kind)
+                            .data()
+                            .as_ref()
+                            .unwrap()
+                            .type_()
+                            .type_()
+                            .cloned()
+                            .unwrap_or(ValueType::Poisoned),
+                        None => ValueType::Number,
+                    });
+                    let ident = &param.leaves(tree)[0];
+                    self.increase_shadowing(&self.source[ident.span()]);
+                }
+                // lambdas infer their return type from their body
+                self.returns.push(None);
+                let body = self.analyze_element(tree, body);
+                self.returns.pop();
+                types.push(
+                    body.get_from_builder(&self.builder)
+                        .type_()
+                        .type_()
+                        .cloned()
+                        .unwrap_or(ValueType::Poisoned),
+                );
+                self.exit_scope();
+                self.analyze_element(tree, captures);
+                self.builder.finish_node(node.span().end, |id| {
+                    Some(NodeData::new(NodeKind::Value(Value {
+                        value: None,
+                        syntax: TreeElement::Node(id),
+                        type_: MaybeTyped::Typed(ValueType::Closure(types)),
+                    })))
+                })
+            }
             If => {
                 self.builder.start_node(node.kind(), node.span().start);
                 let mut iter = node.children_with_leaves(tree);
@@ -330,7 +1535,17 @@ impl<'a> TypeChecker<'a> {
                 let cond = self.analyze_element(tree, a).into_node().unwrap();
                 self.analyze_element(tree, b);
                 c.map(|e| self.analyze_element(tree, e));
+                let value = self
+                    .truth(TreeElement::Node(cond))
+                    .or_else(|| self.constant(TreeElement::Node(cond)).map(|n| n != 0));
                 let cond = cond.get_from_builder(&self.builder);
+                if let Some(value) = value {
+                    self.warnings.push(Error::warning(
+                        ErrorKind::ConstantCondition { value },
+                        cond.span(),
+                        self.source,
+                    ));
+                }
                 let type_ = cond.data().as_ref().unwrap().type_();
                 if let Some(a) = type_.type_() {
                     if !a.can_be_bool() {
@@ -393,29 +1608,71 @@ impl<'a> TypeChecker<'a> {
                     })))
                 })
             }
-            Call => {
+            Call | MethodCall => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let mut iter = node.children_with_leaves(tree);
-                let f = self.analyze_element(tree, iter.next().unwrap());
-                let args = iter
+                let mut args = node
+                    .children_with_leaves(tree)
                     .map(|v| self.analyze_element(tree, v))
                     .collect::<Vec<_>>();
-                let f = f.get_from_builder(&self.builder);
-                let type_ = if let MaybeTyped::Typed(ValueType::FnPtr(v)) = f.type_() {
-                    if args.len() != v.len() - 1 {
-                        self.errors.push(Error::error(
-                            ErrorKind::TooManyArgs {
-                                expected: v.len() - 1,
-                                found: args.len(),
-                            },
-                            node.span(),
-                            self.source,
-                        ))
+                if node.kind() == MethodCall {
+                    // the receiver is passed as the first argument
+                    args.swap(0, 1);
+                }
+                let f = args.remove(0).get_from_builder(&self.builder);
+                let type_ = if let MaybeTyped::Typed(ValueType::FnPtr(v) | ValueType::Closure(v)) =
+                    f.type_()
+                {
+                    let (ret, params) = v.split_last().unwrap();
+                    let mut params = params.to_vec();
+                    let variadic = match params.last() {
+                        Some(ValueType::Variadic(t)) => Some((**t).clone()),
+                        _ => None,
+                    };
+                    if variadic.is_some() {
+                        params.pop();
                     }
-                    for (arg, t) in args.into_iter().zip(v[..v.len() - 1].iter()) {
+                    let required = params
+                        .iter()
+                        .filter(|t| !matches!(t, ValueType::Default(_)))
+                        .count();
+                    let error = if variadic.is_none() && required == params.len() {
+                        (args.len() != required).then_some(ErrorKind::TooManyArgs {
+                            expected: required,
+                            found: args.len(),
+                        })
+                    } else if args.len() < required {
+                        Some(ErrorKind::TooFewArgs {
+                            expected: required,
+                            found: args.len(),
+                        })
+                    } else if variadic.is_none() && args.len() > params.len() {
+                        Some(ErrorKind::TooManyArgs {
+                            expected: params.len(),
+                            found: args.len(),
+                        })
+                    } else {
+                        None
+                    };
+                    if let Some(error) = error.filter(|_| !self.misnamed.contains(&node.id())) {
+                        self.errors
+                            .push(Error::error(error, node.span(), self.source));
+                    }
+                    for t in &mut params {
+                        if let ValueType::Default(inner) = t {
+                            *t = (**inner).clone();
+                        }
+                    }
+                    // the extra arguments all have the type of the variadic parameter
+                    if let Some(t) = variadic {
+                        params.resize(args.len().max(params.len()), t);
+                    }
+                    for (arg, t) in args.into_iter().zip(params.iter()) {
                         let arg = arg.get_from_builder(&self.builder);
                         let arg_t = arg.type_().type_().unwrap();
-                        if arg_t != t && *arg_t != ValueType::Poisoned {
+                        // chars can be given for numbers, as to `putchar`
+                        let char_number = (arg_t, t) == (&ValueType::Char, &ValueType::Number);
+                        if arg_t != t && ![arg_t, t].contains(&&ValueType::Poisoned) && !char_number
+                        {
                             self.errors.push(Error::error(
                                 ErrorKind::WrongType {
                                     expected: t.clone(),
@@ -426,10 +1683,16 @@ impl<'a> TypeChecker<'a> {
                             ));
                         }
                     }
-                    MaybeTyped::Typed(v.last().cloned().unwrap())
+                    MaybeTyped::Typed(ret.clone())
                 } else {
-                    self.errors
-                        .push(Error::error(ErrorKind::NotCallable, f.span(), self.source));
+                    // an undefined callee was already reported
+                    if f.type_() != &MaybeTyped::Typed(ValueType::Poisoned) {
+                        self.errors.push(Error::error(
+                            ErrorKind::NotCallable,
+                            f.span(),
+                            self.source,
+                        ));
+                    }
                     MaybeTyped::Typed(ValueType::Poisoned)
                 };
                 self.builder.finish_node(node.span().end, |id| {
@@ -462,20 +1725,9 @@ impl<'a> TypeChecker<'a> {
             }
             Kind => {
                 self.builder.start_node(node.kind(), node.span().start);
-                let type_ = match node
-                    .children_with_leaves(tree)
-                    .next()
-                    .unwrap()
-                    .into_leaf()
-                    .unwrap()
-                    .get_from_builder(&self.builder)
-                    .kind()
-                {
-                    ByteKw => MaybeTyped::Typed(ValueType::Number),
-                    SemiColon => MaybeTyped::Typed(ValueType::None),
-                    Identifier => todo!(),
-                    _ => unreachable!(),
-                };
+                // names of types are not variables
+                self.copy_children(tree, node);
+                let type_ = node.data().as_ref().unwrap().type_().clone();
                 self.builder.finish_node(node.span().end, |id| {
                     Some(NodeData::new(NodeKind::Value(Value {
                         value: None,
@@ -501,8 +1753,14 @@ impl<'a> TypeChecker<'a> {
         }
     }
 
-    fn analyze_leaf(&mut self, _tree: &AnalyzedTree, leaf: &Leaf<LeafData>) -> LeafId {
+    fn analyze_leaf(&mut self, tree: &AnalyzedTree, leaf: &Leaf<LeafData>) -> LeafId {
         let mut data = leaf.data().clone();
+        let value = match leaf.kind() {
+            Identifier => self
+                .get(&self.source[leaf.span()])
+                .and_then(|v| v.value.clone()),
+            _ => None,
+        };
         let t = if leaf.kind() == Identifier {
             Some(
                 match self
@@ -517,11 +1775,7 @@ impl<'a> TypeChecker<'a> {
                 {
                     Some(v) => v,
                     None => {
-                        self.errors.push(Error::error(
-                            ErrorKind::UndefinedReference,
-                            leaf.span(),
-                            self.source,
-                        ));
+                        self.undefined(tree, leaf);
                         MaybeTyped::Typed(ValueType::Poisoned)
                     }
                 },
@@ -534,9 +1788,52 @@ impl<'a> TypeChecker<'a> {
                 if let Some(LeafKind::Value(v)) = data.as_mut().map(|v| &mut v.kind) {
                     v.type_ = t;
                     v.syntax = TreeElement::Leaf(id);
+                    v.value = value;
                 }
             }
             data
         })
     }
 }
+
+/// Result of a comparison of numbers known at compile time
+fn compare(op: SyntaxKind, a: u32, b: u32) -> Option<bool> {
+    match op {
+        Equal => Some(a == b),
+        NotEqual => Some(a != b),
+        LessThan => Some(a < b),
+        GreaterThan => Some(a > b),
+        LessEqual => Some(a <= b),
+        GreaterEqual => Some(a >= b),
+        _ => None,
+    }
+}
+
+/// Result of a binary operation on booleans known at compile time
+fn logic(op: SyntaxKind, a: bool, b: bool) -> Option<bool> {
+    match op {
+        And => Some(a & b),
+        Or => Some(a | b),
+        Xor | NotEqual => Some(a ^ b),
+        Equal => Some(a == b),
+        _ => None,
+    }
+}
+
+/// Result of a binary operation on numbers known at compile time, if it is a number
+fn fold(op: SyntaxKind, a: u32, b: u32) -> Option<u32> {
+    match op {
+        Add => a.checked_add(b),
+        Sub => a.checked_sub(b),
+        Mul => a.checked_mul(b),
+        Div => a.checked_div(b),
+        Mod => a.checked_rem(b),
+        Pow => a.checked_pow(b),
+        And => Some(a & b),
+        Or => Some(a | b),
+        Xor => Some(a ^ b),
+        Shl => a.checked_shl(b).filter(|n| n >> b == a),
+        Shr => a.checked_shr(b),
+        _ => None,
+    }
+}
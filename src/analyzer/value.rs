@@ -5,7 +5,6 @@ use once_cell::sync::Lazy;
 use crate::{
     parser::syntax::SyntaxKind,
     tree::{Leaf, LeafId, Node, NodeId, TreeElement},
-    Span,
 };
 
 #[derive(Debug, Clone)]
@@ -76,6 +75,10 @@ pub enum AssignLHS {
     Invalid,
     Ident,
     Deref(TreeElement<NodeId, LeafId>),
+    /// Element of an array, stores the index node
+    Index(TreeElement<NodeId, LeafId>),
+    /// Field of a struct, stores the access node
+    Field(TreeElement<NodeId, LeafId>),
 }
 
 impl NodeData {
@@ -108,7 +111,7 @@ impl Display for NodeData {
 #[derive(Debug, Clone)]
 pub enum NodeKind {
     Value(Value),
-    Kind(ValueType),
+    Kind(MaybeTyped),
 }
 
 impl Display for NodeKind {
@@ -123,7 +126,7 @@ impl Display for NodeKind {
 impl NodeKind {
     pub fn type_(&self) -> &MaybeTyped {
         match self {
-            NodeKind::Kind(_v) => todo!(),
+            NodeKind::Kind(type_) => type_,
             NodeKind::Value(v) => &v.type_,
         }
     }
@@ -148,20 +151,6 @@ impl<'a> TreeElement<&'a Node<NodeData>, &'a Leaf<LeafData>> {
         }
     }
 
-    pub fn span(&self) -> Span {
-        match self {
-            TreeElement::Node(node) => node.span(),
-            TreeElement::Leaf(leaf) => leaf.span(),
-        }
-    }
-
-    pub fn kind(&self) -> SyntaxKind {
-        match self {
-            TreeElement::Node(node) => node.kind(),
-            TreeElement::Leaf(leaf) => leaf.kind(),
-        }
-    }
-
     pub fn data(&self) -> TreeElement<&'a NodeData, &'a LeafData> {
         match self {
             TreeElement::Node(node) => TreeElement::Node(node.data().as_ref().unwrap()),
@@ -189,15 +178,39 @@ pub enum MaybeTyped {
 pub enum ValueType {
     None,
     Number,
+    /// Double precision float, stored as its bits
+    Float,
+    /// Byte of a character, it becomes a number when operated on
+    Char,
+    Boolean,
+    /// Null terminated string of bytes
+    String,
     Poisoned,
     Pointer(Box<ValueType>),
+    /// Type of the elements and the length
+    Array(Box<ValueType>, usize),
+    /// Types of the elements
+    Tuple(Vec<ValueType>),
+    /// Name and fields of the struct
+    Struct(String, Vec<(String, ValueType)>),
+    /// Name and variants of the enum with the types of their payload
+    Enum(String, Vec<(String, Vec<ValueType>)>),
     FnPtr(Vec<ValueType>),
+    /// Type of the extra arguments collected by a variadic parameter, they are preceded by their
+    /// count at runtime
+    Variadic(Box<ValueType>),
+    /// Type of a parameter that has a default value
+    Default(Box<ValueType>),
+    /// Types of the parameters and of the returned value of a lambda
+    Closure(Vec<ValueType>),
 }
 
 #[derive(Debug, Clone)]
 pub enum ValueData {
     None,
     Number(u32),
+    Float(f64),
+    Bool(bool),
     Char(u8),
     String(Vec<u8>),
     /// References to the array elements
@@ -220,8 +233,36 @@ impl Display for MaybeTyped {
 
 impl ValueType {
     pub fn operate_binary(&self, op: SyntaxKind, other: &ValueType) -> Option<ValueType> {
+        use SyntaxKind::*;
         match (&self, op, other) {
+            (
+                ValueType::Number,
+                Equal | NotEqual | LessThan | GreaterThan | LessEqual | GreaterEqual,
+                ValueType::Number,
+            )
+            | (ValueType::Boolean, Equal | NotEqual | And | Or | Xor, ValueType::Boolean) => {
+                Some(ValueType::Boolean)
+            }
             (ValueType::Number, _, ValueType::Number) => Some(ValueType::Number),
+            (
+                ValueType::Char,
+                Equal | NotEqual | LessThan | GreaterThan | LessEqual | GreaterEqual,
+                ValueType::Char,
+            ) => Some(ValueType::Boolean),
+            (
+                ValueType::Number | ValueType::Char,
+                Add | Sub | Mul | Pow | Div | Mod | And | Or | Xor | Shl | Shr,
+                ValueType::Number | ValueType::Char,
+            ) => Some(ValueType::Number),
+            (
+                ValueType::Float,
+                Equal | NotEqual | LessThan | GreaterThan | LessEqual | GreaterEqual,
+                ValueType::Float,
+            ) => Some(ValueType::Boolean),
+            (ValueType::Float, Add | Sub | Mul | Div | Mod, ValueType::Float) => {
+                Some(ValueType::Float)
+            }
+            (ValueType::String, SyntaxKind::Add, ValueType::String) => Some(ValueType::String),
             (ValueType::Poisoned, _, _) | (_, _, ValueType::Poisoned) => Some(ValueType::Poisoned),
             _ => None,
         }
@@ -230,20 +271,74 @@ impl ValueType {
     pub fn operate_unary(&self, op: SyntaxKind) -> Option<ValueType> {
         match (self, op) {
             (ValueType::Number, SyntaxKind::Not | SyntaxKind::Sub) => Some(ValueType::Number),
+            (ValueType::Float, SyntaxKind::Sub) => Some(ValueType::Float),
+            (ValueType::Boolean, SyntaxKind::Not) => Some(ValueType::Boolean),
             (ValueType::Poisoned, _) => Some(ValueType::Poisoned),
             (ValueType::Pointer(t), SyntaxKind::Mul) => Some((**t).clone()),
             _ => None,
         }
     }
 
+    /// Type of the elements when indexed
+    pub fn index(&self) -> Option<ValueType> {
+        match self {
+            ValueType::Array(t, _) | ValueType::Variadic(t) => Some((**t).clone()),
+            ValueType::Poisoned => Some(ValueType::Poisoned),
+            _ => None,
+        }
+    }
+
+    /// Type of a field when accessed, tuple elements are accessed by position and the extra
+    /// arguments of a variadic function know their `len`
+    pub fn field(&self, field: &str) -> Option<ValueType> {
+        match self {
+            ValueType::Tuple(types) => types.get(field.parse::<usize>().ok()?).cloned(),
+            ValueType::Struct(_, fields) => fields
+                .iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, t)| t.clone()),
+            ValueType::Variadic(_) if field == "len" => Some(ValueType::Number),
+            ValueType::Poisoned => Some(ValueType::Poisoned),
+            _ => None,
+        }
+    }
+
     pub fn can_be_bool(&self) -> bool {
-        matches!(self, ValueType::Number)
+        matches!(
+            self,
+            ValueType::Number | ValueType::Boolean | ValueType::Poisoned
+        )
+    }
+
+    /// Whether a value of this type can be cast to `other`, floats are converted from and to
+    /// numbers while the other casts keep the bits of the value
+    pub fn can_cast(&self, other: &ValueType) -> bool {
+        use ValueType::*;
+        match (self, other) {
+            (Poisoned, _) | (_, Poisoned) => true,
+            (Number | Char | Boolean, Number | Char | Boolean) => true,
+            (Number, Float) | (Float, Number) => true,
+            (Pointer(_), Pointer(_)) | (Pointer(_), Number) | (Number, Pointer(_)) => true,
+            (a, b) => a == b,
+        }
+    }
+
+    /// Whether a value of this type can be stored where a `kind` is expected, an empty array
+    /// literal having no element type of its own
+    pub fn fits(&self, kind: &ValueType) -> bool {
+        use ValueType::*;
+        match (self, kind) {
+            (Array(a, 0), Array(_, 0)) if **a == None => true,
+            (Array(a, n), Array(b, m)) => n == m && a.fits(b),
+            (Tuple(a), Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.fits(b)),
+            (a, b) => a == b,
+        }
     }
 
     pub fn can_be_displayed(&self) -> bool {
         match self {
             ValueType::Pointer(x) if **x == ValueType::Number => true,
-            ValueType::Number => true,
+            ValueType::Number | ValueType::Float | ValueType::String => true,
             _ => false,
         }
     }
@@ -275,9 +370,28 @@ impl Display for ValueType {
         match self {
             ValueType::None => write!(f, "None"),
             ValueType::Number => write!(f, "Number"),
+            ValueType::Float => write!(f, "Float"),
+            ValueType::Char => write!(f, "Char"),
+            ValueType::Boolean => write!(f, "Boolean"),
+            ValueType::String => write!(f, "String"),
             ValueType::Pointer(t) => write!(f, "*{t}"),
+            ValueType::Array(t, n) => write!(f, "[{t}; {n}]"),
+            ValueType::Tuple(types) => {
+                write!(f, "(")?;
+                for (i, t) in types.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{t}")?;
+                }
+                write!(f, ")")
+            }
+            ValueType::Struct(name, _) | ValueType::Enum(name, _) => write!(f, "{name}"),
             ValueType::Poisoned => write!(f, "\u{1F480}"),
             ValueType::FnPtr(v) => write!(f, "fn {v:?}"),
+            ValueType::Closure(v) => write!(f, "closure {v:?}"),
+            ValueType::Variadic(t) => write!(f, "...{t}"),
+            ValueType::Default(t) => write!(f, "{t} = _"),
         }
     }
 }
@@ -287,6 +401,8 @@ impl Display for ValueData {
         match self {
             ValueData::None => write!(f, "None"),
             ValueData::Number(n) => write!(f, "{n}"),
+            ValueData::Float(n) => write!(f, "{n:?}"),
+            ValueData::Bool(b) => write!(f, "{b}"),
             &ValueData::Char(c) => write!(f, "{:?}", c as char),
             ValueData::String(s) => write!(f, "{:?}", std::str::from_utf8(s).unwrap()),
             ValueData::Array(_) => todo!(),
@@ -331,7 +447,7 @@ pub static BUILT_INS: Lazy<HashMap<&'static str, BuiltInFunc>> = Lazy::new(|| {
     built_in.insert(
         "puts",
         BuiltInFunc {
-            args: vec![ValueType::Pointer(Box::new(ValueType::Number))],
+            args: vec![ValueType::String],
             ret: Some(ValueType::Number),
         },
     );
@@ -344,6 +460,13 @@ pub static BUILT_INS: Lazy<HashMap<&'static str, BuiltInFunc>> = Lazy::new(|| {
     );
     built_in.insert(
         "getchar",
+        BuiltInFunc {
+            args: vec![],
+            ret: Some(ValueType::Char),
+        },
+    );
+    built_in.insert(
+        "getnum",
         BuiltInFunc {
             args: vec![],
             ret: Some(ValueType::Number),
@@ -1,19 +1,43 @@
 mod translator;
-mod utils;
 
 use cranelift::prelude::{
     codegen, settings, AbiParam, EntityRef, FunctionBuilder, FunctionBuilderContext, InstBuilder,
     Variable,
 };
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::{DataContext, Linkage, Module, ModuleError};
-use std::slice;
+use cranelift_module::{DataContext, FuncId, Linkage, Module, ModuleError};
+use std::{collections::HashMap, slice};
 
 use crate::analyzer::value::BUILT_INS;
 use crate::analyzer::{value::ValueType, Analyzed, AnalyzedTree};
 
 use translator::FunctionTranslator;
 
+/// Exit code of a program stopped by a failing `assert`
+pub const ASSERTION_FAILED: u8 = 4;
+
+extern "C" {
+    fn getchar() -> i32;
+    fn fflush(stream: *mut std::ffi::c_void) -> i32;
+}
+
+/// Flushes what the program wrote through the C library, which buffers it apart from Rust
+pub fn flush() {
+    unsafe { fflush(std::ptr::null_mut()) };
+}
+
+/// Built-in reading a decimal number from the standard input, up to the first character that
+/// isn't a digit. It reads through `getchar` so that it shares its buffer with the program
+extern "C" fn getnum() -> i64 {
+    let mut n = 0i64;
+    loop {
+        match u8::try_from(unsafe { getchar() }) {
+            Ok(c @ b'0'..=b'9') => n = n.wrapping_mul(10).wrapping_add((c - b'0') as i64),
+            _ => return n,
+        }
+    }
+}
+
 pub struct JIT<'a> {
     builder_context: FunctionBuilderContext,
     ctx: codegen::Context,
@@ -24,9 +48,16 @@ pub struct JIT<'a> {
 
 impl<'a> JIT<'a> {
     pub fn new(source: &'a str) -> Self {
-        let builder = JITBuilder::new(cranelift_module::default_libcall_names());
-
-        let module = JITModule::new(builder.unwrap());
+        // lets Cranelift remove dead stores and other redundant instructions
+        let mut builder = JITBuilder::with_flags(
+            &[("opt_level", "speed")],
+            cranelift_module::default_libcall_names(),
+        )
+        .unwrap();
+        // the built-ins missing from libc
+        builder.symbol("getnum", getnum as *const u8);
+
+        let module = JITModule::new(builder);
         Self {
             builder_context: FunctionBuilderContext::new(),
             ctx: module.make_context(),
@@ -36,9 +67,9 @@ impl<'a> JIT<'a> {
         }
     }
 
+    #[allow(clippy::result_large_err)]
     pub fn compile(&mut self, analyzed: Analyzed) -> Result<fn() -> i64, ModuleError> {
-        self.translate(analyzed)?;
-        println!("{}", self.ctx.func);
+        let functions = self.translate(analyzed)?;
 
         let id =
             self.module
@@ -48,13 +79,29 @@ impl<'a> JIT<'a> {
         cranelift::codegen::verifier::verify_function(&self.ctx.func, &flags).unwrap();
 
         self.module.define_function(id, &mut self.ctx)?;
-
         self.module.clear_context(&mut self.ctx);
+
+        for (id, mut ctx) in functions {
+            self.module.define_function(id, &mut ctx)?;
+        }
         self.module.finalize_definitions()?;
 
         let code = self.module.get_finalized_function(id);
 
-        Ok(unsafe { std::mem::transmute(code) })
+        Ok(unsafe { std::mem::transmute::<*const u8, fn() -> i64>(code) })
+    }
+
+    /// Translates the program and returns its Cranelift IR without compiling it
+    #[allow(clippy::result_large_err)]
+    pub fn ir(&mut self, analyzed: Analyzed) -> Result<String, ModuleError> {
+        let functions = self.translate(analyzed)?;
+        let mut ir = self.ctx.func.to_string();
+        for (_, ctx) in functions {
+            ir.push('\n');
+            ir.push_str(&ctx.func.to_string());
+        }
+        self.module.clear_context(&mut self.ctx);
+        Ok(ir)
     }
 
     pub fn create_data(&mut self, name: &str, contents: Vec<u8>) -> Result<&[u8], String> {
@@ -75,7 +122,13 @@ impl<'a> JIT<'a> {
         Ok(unsafe { slice::from_raw_parts(buffer.0, buffer.1) })
     }
 
-    fn translate(&mut self, analyzed: Analyzed) -> Result<(), ModuleError> {
+    /// Translates the program into the main function of the context.
+    /// Returns the functions defined by the program, to be defined in the module
+    #[allow(clippy::result_large_err)]
+    fn translate(
+        &mut self,
+        analyzed: Analyzed,
+    ) -> Result<Vec<(FuncId, codegen::Context)>, ModuleError> {
         let int = self.module.target_config().pointer_type();
         self.ctx.func.signature.returns.push(AbiParam::new(int));
 
@@ -89,52 +142,51 @@ impl<'a> JIT<'a> {
             tree,
             memory,
             mut lookup,
+            ..
         } = analyzed;
 
-        let mut variables = memory
+        let variables = memory
             .into_iter()
             .enumerate()
             .map(|(i, _)| Variable::new(i))
-            .map(|v| {
+            .inspect(|&v| {
                 builder.declare_var(v, int);
-                v
             })
             .collect::<Vec<_>>();
+        let mut functions = HashMap::new();
 
-        for (name, f) in BUILT_INS.iter() {
+        let mut built_ins = BUILT_INS.iter().collect::<Vec<_>>();
+        built_ins.sort_unstable_by_key(|&(name, _)| name);
+        for (name, f) in built_ins {
             let mut sig = self.module.make_signature();
             for arg in &f.args {
                 let t = match arg {
-                    ValueType::Number | ValueType::Pointer(_) => int,
+                    ValueType::Number
+                    | ValueType::Char
+                    | ValueType::String
+                    | ValueType::Pointer(_) => int,
                     _ => todo!(),
                 };
                 sig.params.push(AbiParam::new(t));
             }
             if let Some(ret) = &f.ret {
                 let t = match ret {
-                    ValueType::Number | ValueType::Pointer(_) => int,
+                    ValueType::Number
+                    | ValueType::Char
+                    | ValueType::String
+                    | ValueType::Pointer(_) => int,
                     _ => todo!(),
                 };
                 sig.returns.push(AbiParam::new(t));
             }
 
-            let callee = self
-                .module
-                .declare_function(name, cranelift_module::Linkage::Import, &sig)
-                .unwrap();
-
-            let local_callee = self.module.declare_func_in_func(callee, builder.func);
-
-            let val = builder.ins().func_addr(int, local_callee);
-            let var = Variable::new(variables.len());
-            builder.declare_var(var, int);
-            builder.def_var(var, val);
-            lookup[0]
-                .entry(name)
-                .or_insert((Vec::new(), 1))
-                .0
-                .push(variables.len());
-            variables.push(var);
+            let callee =
+                self.module
+                    .declare_function(name, cranelift_module::Linkage::Import, &sig)?;
+
+            let loc = variables.len() + functions.len();
+            lookup[0].entry(name).or_insert((Vec::new(), 1)).0.push(loc);
+            functions.insert(loc, callee);
         }
 
         let mut return_var = builder.ins().iconst(int, 0);
@@ -143,18 +195,26 @@ impl<'a> JIT<'a> {
             source: self.source,
             int,
             builder,
+            module: &mut self.module,
             lookup,
             variables,
-            current_scope: 0,
+            functions,
+            definitions: Vec::new(),
+            declared: HashMap::new(),
+            scopes: vec![0],
+            entered: 0,
+            loops: Vec::new(),
+            recursion: None,
         };
 
         let root = tree.node(AnalyzedTree::ROOT);
+        trans.declare_functions(&tree, root.children());
         for &child in root.children() {
             return_var = trans.translate_node(&tree, tree.node(child));
         }
 
         trans.builder.ins().return_(&[return_var]);
         trans.builder.finalize();
-        Ok(())
+        Ok(trans.definitions)
     }
 }
@@ -1,49 +1,475 @@
 use cranelift::prelude::{
-    types, FunctionBuilder, InstBuilder, IntCC, MemFlags, Value, Variable,
+    codegen, types, AbiParam, Block, EntityRef, FloatCC, FunctionBuilder, FunctionBuilderContext,
+    InstBuilder, IntCC, MemFlags, StackSlotData, StackSlotKind, TrapCode, Value, Variable,
 };
+use cranelift_jit::JITModule;
+use cranelift_module::{DataContext, FuncId, Linkage, Module};
 use std::collections::HashMap;
 
 use crate::{
     analyzer::{
-        value::{AssignLHS, LeafData, NodeData, ValueData, ValueType},
+        value::{
+            AssignLHS, LeafData, LeafKind, MaybeTyped, NodeData, NodeKind, ValueData, ValueType,
+        },
         AnalyzedTree,
     },
+    compiler::ASSERTION_FAILED,
     parser::syntax::SyntaxKind as SK,
     tree::{Leaf, LeafId, Node, NodeId, TreeElement},
 };
 
-pub struct FunctionTranslator<'a> {
+pub struct FunctionTranslator<'a, 'b> {
     pub(crate) int: types::Type,
     pub(crate) source: &'a str,
-    pub(crate) builder: FunctionBuilder<'a>,
+    pub(crate) builder: FunctionBuilder<'b>,
+    pub(crate) module: &'b mut JITModule,
     pub(crate) lookup: Vec<HashMap<&'a str, (Vec<usize>, usize)>>,
     pub(crate) variables: Vec<Variable>,
-    pub(crate) current_scope: usize,
-    // pub(crate) module: &'a mut JITModule,
+    /// Functions bound to memory locations, both built-ins and the ones defined by the program
+    pub(crate) functions: HashMap<usize, FuncId>,
+    /// Functions translated so far, not yet defined in the module
+    pub(crate) definitions: Vec<(FuncId, codegen::Context)>,
+    /// Functions declared before their definition
+    pub(crate) declared: HashMap<NodeId, FuncId>,
+    /// Lookup maps of the enclosing scopes, innermost last
+    pub(crate) scopes: Vec<usize>,
+    /// Number of scopes entered so far
+    pub(crate) entered: usize,
+    /// Labels, continue and exit blocks of the enclosing loops
+    pub(crate) loops: Vec<(Option<&'a str>, Block, Block)>,
+    /// Function being translated with the block of its body and its parameters, its calls to
+    /// itself that are returned jump back to the body instead
+    pub(crate) recursion: Option<(FuncId, Block, Vec<Variable>)>,
 }
 
-impl<'a> FunctionTranslator<'a> {
+impl<'a, 'b> FunctionTranslator<'a, 'b> {
     #[inline]
     fn get(&self, ident: &'a str) -> Variable {
-        self.lookup[..=self.current_scope]
+        self.variables[self.get_loc(ident)]
+    }
+
+    #[inline]
+    fn get_loc(&self, ident: &'a str) -> usize {
+        // names are visible once defined
+        self.scopes
             .iter()
             .rev()
-            .find_map(|map| map.get(ident))
-            .map(|(v, i)| self.variables[v[*i - 1]])
+            .find_map(|&scope| self.lookup[scope].get(ident).filter(|(_, i)| *i != 0))
+            .map(|(v, i)| v[*i - 1])
             .unwrap()
     }
 
     #[inline]
     fn increase_shadowing(&mut self, ident: &'a str) {
-        if let Some((_, i)) = self.lookup[..=self.current_scope]
-            .iter_mut()
-            .rev()
-            .find_map(|map| map.get_mut(ident))
-        {
+        let scope = *self.scopes.last().unwrap();
+        if let Some((_, i)) = self.lookup[scope].get_mut(ident) {
             *i += 1;
         }
     }
 
+    /// Enters the next scope, in the order the analyzer entered them
+    fn enter_scope(&mut self) {
+        self.entered += 1;
+        self.scopes.push(self.entered);
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Declares the functions defined by a list of statements so that they can be called before
+    /// their definition
+    pub fn declare_functions(&mut self, tree: &AnalyzedTree, statements: &[NodeId]) {
+        for &statement in statements {
+            let Some(&fx) = tree.node(statement).children().first() else {
+                continue;
+            };
+            let fx = tree.node(fx);
+            if fx.kind() != SK::Fx {
+                continue;
+            }
+            // the name and the parameters precede the body
+            let leaves = fx
+                .children_with_leaves(tree)
+                .filter_map(|e| e.into_leaf())
+                .map(|l| tree.leaf(l))
+                .collect::<Vec<_>>();
+            let mut sig = self.module.make_signature();
+            // functions with default values are also passed the number of arguments given
+            let defaults = leaves.iter().any(|l| l.kind() == SK::Assign);
+            sig.params.extend(
+                leaves[1..]
+                    .iter()
+                    .filter(|l| l.kind() == SK::Identifier)
+                    .chain(defaults.then_some(&leaves[0]))
+                    .map(|_| AbiParam::new(self.int)),
+            );
+            sig.returns.push(AbiParam::new(self.int));
+            let id = self.module.declare_anonymous_function(&sig).unwrap();
+            let name = &self.source[leaves[0].span()];
+            self.increase_shadowing(name);
+            self.functions.insert(self.get_loc(name), id);
+            self.declared.insert(fx.id(), id);
+        }
+    }
+
+    /// Calls a function from the C standard library taking and returning integers
+    fn call_libc(&mut self, name: &str, args: &[Value]) -> Value {
+        let mut sig = self.module.make_signature();
+        sig.params
+            .extend(args.iter().map(|_| AbiParam::new(self.int)));
+        sig.returns.push(AbiParam::new(self.int));
+        let callee = self
+            .module
+            .declare_function(name, Linkage::Import, &sig)
+            .unwrap();
+        let func_ref = self.module.declare_func_in_func(callee, self.builder.func);
+        let call = self.builder.ins().call(func_ref, args);
+        self.builder.inst_results(call)[0]
+    }
+
+    /// Allocates `size` words on the heap, aggregates can outlive the function creating them
+    fn alloc(&mut self, size: usize) -> Value {
+        let size = self
+            .builder
+            .ins()
+            .iconst(self.int, (self.int.bytes() as usize * size) as i64);
+        self.call_libc("malloc", &[size])
+    }
+
+    /// Copy of an aggregate holding copies of its elements, so that assigning through the place it
+    /// is stored in doesn't change the one it was read from. The other values are left as they are
+    fn copy(&mut self, value: Value, type_: &ValueType) -> Value {
+        let elements = match type_ {
+            ValueType::Array(t, len) => vec![&**t; *len],
+            ValueType::Tuple(types) => types.iter().collect(),
+            ValueType::Struct(_, fields) => fields.iter().map(|(_, t)| t).collect(),
+            _ => return value,
+        };
+        let addr = self.alloc(elements.len());
+        for (i, t) in elements.into_iter().enumerate() {
+            let offset = (self.int.bytes() as usize * i) as i32;
+            let element = self
+                .builder
+                .ins()
+                .load(self.int, MemFlags::new(), value, offset);
+            let element = self.copy(element, t);
+            self.builder
+                .ins()
+                .store(MemFlags::new(), element, addr, offset);
+        }
+        addr
+    }
+
+    /// Translates a value being stored, aggregates that aren't built by the element itself are
+    /// copied
+    fn translate_stored(
+        &mut self,
+        tree: &AnalyzedTree,
+        element: TreeElement<NodeId, LeafId>,
+    ) -> Value {
+        let value = self.translate_element(tree, element);
+        let element = element.get(tree);
+        match (element.kind(), element.type_()) {
+            (SK::List | SK::Tuple | SK::StructLit, _) => value,
+            (_, MaybeTyped::Typed(type_)) => self.copy(value, type_),
+            _ => value,
+        }
+    }
+
+    /// Address of read-only data holding `data`
+    fn data(&mut self, data: Box<[u8]>) -> Value {
+        let id = self.module.declare_anonymous_data(false, false).unwrap();
+        let mut data_ctx = DataContext::new();
+        data_ctx.define(data);
+        self.module.define_data(id, &data_ctx).unwrap();
+        let value = self.module.declare_data_in_func(id, self.builder.func);
+        self.builder.ins().global_value(self.int, value)
+    }
+
+    /// Allocates a new string holding `a` followed by `b`
+    fn concat(&mut self, a: Value, b: Value) -> Value {
+        let len_a = self.call_libc("strlen", &[a]);
+        let len_b = self.call_libc("strlen", &[b]);
+        let len = self.builder.ins().iadd(len_a, len_b);
+        let len = self.builder.ins().iadd_imm(len, 1);
+        let s = self.call_libc("malloc", &[len]);
+        self.call_libc("strcpy", &[s, a]);
+        self.call_libc("strcat", &[s, b]);
+        s
+    }
+
+    /// Compares two integers, yielding 0 or 1
+    fn compare(&mut self, cc: IntCC, a: Value, b: Value) -> Value {
+        let cmp = self.builder.ins().icmp(cc, a, b);
+        self.builder.ins().uextend(self.int, cmp)
+    }
+
+    /// Applies a binary operator on two values of the given type: strings are concatenated,
+    /// floats and integers are operated on with their own instructions
+    fn combine(&mut self, op: SK, type_: &MaybeTyped, a: Value, b: Value) -> Value {
+        match type_ {
+            MaybeTyped::Typed(ValueType::String) => self.concat(a, b),
+            MaybeTyped::Typed(ValueType::Float) => self.operate_float(op, a, b),
+            _ => self.operate(op, a, b),
+        }
+    }
+
+    /// Stores the value at the address, combined with the value already there by a compound
+    /// assignment operator
+    fn store_at(&mut self, addr: Value, op: Option<SK>, type_: &MaybeTyped, mut value: Value) {
+        if let Some(op) = op {
+            let old = self.builder.ins().load(self.int, MemFlags::new(), addr, 0);
+            value = self.combine(op, type_, old, value);
+        }
+        self.builder.ins().store(MemFlags::new(), value, addr, 0);
+    }
+
+    /// Applies a binary operator on two integers
+    fn operate(&mut self, op: SK, a: Value, b: Value) -> Value {
+        match op {
+            SK::Add => self.builder.ins().iadd(a, b),
+            SK::Sub => self.builder.ins().isub(a, b),
+            SK::Mul => self.builder.ins().imul(a, b),
+            SK::Div | SK::Mod => {
+                // dividing by zero gives 0 and leaves the dividend as the remainder
+                let one = self.builder.ins().iconst(self.int, 1);
+                let divisor = self.builder.ins().select(b, b, one);
+                let zero = self.builder.ins().iconst(self.int, 0);
+                if op == SK::Div {
+                    let quotient = self.builder.ins().sdiv(a, divisor);
+                    self.builder.ins().select(b, quotient, zero)
+                } else {
+                    let remainder = self.builder.ins().srem(a, divisor);
+                    self.builder.ins().select(b, remainder, a)
+                }
+            }
+            SK::And => self.builder.ins().band(a, b),
+            SK::Or => self.builder.ins().bor(a, b),
+            SK::Xor => self.builder.ins().bxor(a, b),
+            SK::Shl | SK::Shr => {
+                // shifting by the width of a number or more shifts every bit out, instead of
+                // wrapping the amount around
+                let bits = self.int.bits() as i64;
+                let wide = self
+                    .builder
+                    .ins()
+                    .icmp_imm(IntCC::UnsignedGreaterThanOrEqual, b, bits);
+                if op == SK::Shl {
+                    let shifted = self.builder.ins().ishl(a, b);
+                    let zero = self.builder.ins().iconst(self.int, 0);
+                    self.builder.ins().select(wide, zero, shifted)
+                } else {
+                    let last = self.builder.ins().iconst(self.int, bits - 1);
+                    let amount = self.builder.ins().select(wide, last, b);
+                    self.builder.ins().sshr(a, amount)
+                }
+            }
+            SK::Equal => self.compare(IntCC::Equal, a, b),
+            SK::NotEqual => self.compare(IntCC::NotEqual, a, b),
+            SK::GreaterThan => self.compare(IntCC::SignedGreaterThan, a, b),
+            SK::GreaterEqual => self.compare(IntCC::SignedGreaterThanOrEqual, a, b),
+            SK::LessThan => self.compare(IntCC::SignedLessThan, a, b),
+            SK::LessEqual => self.compare(IntCC::SignedLessThanOrEqual, a, b),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Raises `a` to the power of `b`, squaring and multiplying are unrolled when the exponent is
+    /// known and looped over its bits otherwise
+    fn power(&mut self, a: Value, b: Value, exponent: Option<u32>) -> Value {
+        if let Some(mut exponent) = exponent {
+            let mut result = None;
+            let mut base = a;
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    result = Some(match result {
+                        Some(result) => self.builder.ins().imul(result, base),
+                        None => base,
+                    });
+                }
+                exponent >>= 1;
+                if exponent > 0 {
+                    base = self.builder.ins().imul(base, base);
+                }
+            }
+            return result.unwrap_or_else(|| self.builder.ins().iconst(self.int, 1));
+        }
+
+        let header_block = self.builder.create_block();
+        let body_block = self.builder.create_block();
+        let exit_block = self.builder.create_block();
+        self.builder.append_block_param(header_block, self.int);
+        self.builder.append_block_param(header_block, self.int);
+        self.builder.append_block_param(header_block, self.int);
+        self.builder.append_block_param(exit_block, self.int);
+        let one = self.builder.ins().iconst(self.int, 1);
+        self.builder.ins().jump(header_block, &[one, a, b]);
+
+        self.builder.switch_to_block(header_block);
+        let &[result, base, exponent] = self.builder.block_params(header_block) else {
+            unreachable!()
+        };
+        self.builder
+            .ins()
+            .brif(exponent, body_block, &[], exit_block, &[result]);
+
+        self.builder.switch_to_block(body_block);
+        self.builder.seal_block(body_block);
+        // the exponent is taken as unsigned so that the loop always ends
+        let bit = self.builder.ins().band_imm(exponent, 1);
+        let multiplied = self.builder.ins().imul(result, base);
+        let result = self.builder.ins().select(bit, multiplied, result);
+        let base = self.builder.ins().imul(base, base);
+        let exponent = self.builder.ins().ushr_imm(exponent, 1);
+        self.builder
+            .ins()
+            .jump(header_block, &[result, base, exponent]);
+        self.builder.seal_block(header_block);
+
+        self.builder.switch_to_block(exit_block);
+        self.builder.seal_block(exit_block);
+        self.builder.block_params(exit_block)[0]
+    }
+
+    /// Body and parameters of the function being translated if the node calls it with numbers,
+    /// booleans, chars or floats, which don't point into its stack frame
+    fn tail_call(
+        &self,
+        tree: &AnalyzedTree,
+        node: &Node<NodeData>,
+    ) -> Option<(Block, Vec<Variable>)> {
+        let (id, body_block, variables) = self.recursion.as_ref()?;
+        if node.kind() != SK::Call {
+            return None;
+        }
+        let mut callee = node.children_with_leaves(tree).next()?;
+        let MaybeTyped::Typed(ValueType::FnPtr(types)) = callee.get(tree).type_() else {
+            return None;
+        };
+        let scalar = types[..types.len() - 1].iter().all(|t| {
+            matches!(
+                t,
+                ValueType::Number | ValueType::Float | ValueType::Char | ValueType::Boolean
+            )
+        });
+        while let TreeElement::Node(value) = callee {
+            let value = tree.node(value);
+            if value.kind() != SK::Value {
+                return None;
+            }
+            callee = value.children_with_leaves(tree).next()?;
+        }
+        let name = callee.get(tree);
+        let called = name.kind() == SK::Identifier
+            && self.functions.get(&self.get_loc(&self.source[name.span()])) == Some(id);
+        (scalar && called).then(|| (*body_block, variables.clone()))
+    }
+
+    /// Value of an element if it is a number known at compile time
+    fn constant(tree: &AnalyzedTree, element: TreeElement<NodeId, LeafId>) -> Option<u32> {
+        let value = match element.get(tree) {
+            TreeElement::Node(node) if node.kind() == SK::Value => {
+                return Self::constant(tree, node.children_with_leaves(tree).next()?)
+            }
+            TreeElement::Node(node) => match node.data().as_deref()? {
+                NodeKind::Value(value) => &value.value,
+                NodeKind::Kind(_) => return None,
+            },
+            TreeElement::Leaf(leaf) => match leaf.data().as_deref()? {
+                LeafKind::Value(value) => &value.value,
+                _ => return None,
+            },
+        };
+        match value {
+            Some(ValueData::Number(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Value of an operation on constants, folded by the type checker
+    fn folded(node: &Node<NodeData>) -> Option<i64> {
+        match node.data().as_deref()? {
+            NodeKind::Value(value) => match value.value {
+                Some(ValueData::Number(n)) => Some(n as i64),
+                Some(ValueData::Bool(b)) => Some(b as i64),
+                _ => None,
+            },
+            NodeKind::Kind(_) => None,
+        }
+    }
+
+    /// Applies a binary operator on two floats, which are kept in integers between operations
+    fn operate_float(&mut self, op: SK, a: Value, b: Value) -> Value {
+        let a = self.builder.ins().bitcast(types::F64, MemFlags::new(), a);
+        let b = self.builder.ins().bitcast(types::F64, MemFlags::new(), b);
+        let cc = match op {
+            SK::Equal => FloatCC::Equal,
+            SK::NotEqual => FloatCC::NotEqual,
+            SK::GreaterThan => FloatCC::GreaterThan,
+            SK::GreaterEqual => FloatCC::GreaterThanOrEqual,
+            SK::LessThan => FloatCC::LessThan,
+            SK::LessEqual => FloatCC::LessThanOrEqual,
+            _ => {
+                let res = match op {
+                    SK::Add => self.builder.ins().fadd(a, b),
+                    SK::Sub => self.builder.ins().fsub(a, b),
+                    SK::Mul => self.builder.ins().fmul(a, b),
+                    SK::Div => self.builder.ins().fdiv(a, b),
+                    SK::Mod => {
+                        // a - trunc(a / b) * b
+                        let div = self.builder.ins().fdiv(a, b);
+                        let div = self.builder.ins().trunc(div);
+                        let mul = self.builder.ins().fmul(div, b);
+                        self.builder.ins().fsub(a, mul)
+                    }
+                    _ => unreachable!(),
+                };
+                return self.builder.ins().bitcast(self.int, MemFlags::new(), res);
+            }
+        };
+        let cmp = self.builder.ins().fcmp(cc, a, b);
+        self.builder.ins().uextend(self.int, cmp)
+    }
+
+    /// Address of the element accessed by an index node
+    fn index_addr(&mut self, tree: &AnalyzedTree, node: &Node<NodeData>) -> Value {
+        let mut iter = node.children_with_leaves(tree);
+        let object = iter.next().unwrap();
+        let mut base = self.translate_element(tree, object);
+        if let MaybeTyped::Typed(ValueType::Variadic(_)) = object.get(tree).type_() {
+            // the extra arguments follow their count
+            base = self.builder.ins().iadd_imm(base, self.int.bytes() as i64);
+        }
+        let index = self.translate_element(tree, iter.next().unwrap());
+        let offset = self.builder.ins().imul_imm(index, self.int.bytes() as i64);
+        self.builder.ins().iadd(base, offset)
+    }
+
+    /// Address of the field accessed by an access node
+    fn field_addr(&mut self, tree: &AnalyzedTree, node: &Node<NodeData>) -> Value {
+        let mut iter = node.children_with_leaves(tree);
+        let object = iter.next().unwrap();
+        let name = &self.source[iter.next().unwrap().get(tree).span()];
+        let i = match object.get(tree).type_().type_().unwrap() {
+            ValueType::Struct(_, fields) => fields.iter().position(|(f, _)| f == name).unwrap(),
+            ValueType::Tuple(_) => name.parse().unwrap(),
+            // the only field of the extra arguments is their count
+            ValueType::Variadic(_) => 0,
+            _ => unreachable!(),
+        };
+        let base = self.translate_element(tree, object);
+        self.builder
+            .ins()
+            .iadd_imm(base, (self.int.bytes() as usize * i) as i64)
+    }
+
+    /// Label attached to a loop, break or continue
+    fn label(&self, tree: &AnalyzedTree, node: &Node<NodeData>) -> Option<&'a str> {
+        let label = tree.node(*node.children().first()?);
+        (label.kind() == SK::Label).then(|| &self.source[label.leaves(tree)[0].span()])
+    }
+
     pub fn translate_element(
         &mut self,
         tree: &AnalyzedTree,
@@ -65,50 +491,46 @@ impl<'a> FunctionTranslator<'a> {
                 ret
             }
             SK::Scope => {
-                self.current_scope += 1;
+                self.enter_scope();
+                self.declare_functions(tree, node.children());
                 let mut ret = self.builder.ins().iconst(self.int, 0);
                 for &child in node.children() {
                     ret = self.translate_node(tree, tree.node(child));
                 }
-                self.current_scope -= 1;
+                self.exit_scope();
                 ret
             }
+            SK::BinaryOp | SK::UnaryOp if Self::folded(node).is_some() => {
+                let value = Self::folded(node).unwrap();
+                self.builder.ins().iconst(self.int, value)
+            }
             SK::BinaryOp => {
                 let mut iter = node.children_with_leaves(tree);
                 let e_a = iter.next().unwrap();
                 let op = iter.next().unwrap().into_leaf().unwrap().get(tree).kind();
                 let e_b = iter.next().unwrap();
+                let exponent = Self::constant(tree, e_b).filter(|_| op == SK::Pow);
                 let a = self.translate_element(tree, e_a);
                 let b = self.translate_element(tree, e_b);
-                match op {
-                    SK::Add => self.builder.ins().iadd(a, b),
-                    SK::Sub => self.builder.ins().isub(a, b),
-                    SK::Mul => self.builder.ins().imul(a, b),
-                    SK::Div => self.builder.ins().sdiv(a, b),
-                    SK::Mod => self.builder.ins().srem(a, b),
-                    SK::And => self.builder.ins().band(a, b),
-                    SK::Or => self.builder.ins().bor(a, b),
-                    SK::Xor => self.builder.ins().bxor(a, b),
-                    SK::Shl => self.builder.ins().ishl(a, b),
-                    SK::Shr => self.builder.ins().sshr(a, b),
-                    SK::Equal => self.builder.ins().icmp(IntCC::Equal, a, b),
-                    SK::NotEqual => self.builder.ins().icmp(IntCC::NotEqual, a, b),
-                    SK::GreaterThan => self.builder.ins().icmp(IntCC::SignedGreaterThan, a, b),
-                    SK::GreaterEqual => {
-                        self.builder
-                            .ins()
-                            .icmp(IntCC::SignedGreaterThanOrEqual, a, b)
-                    }
-                    SK::LessThan => self.builder.ins().icmp(IntCC::SignedLessThan, a, b),
-                    SK::LessEqual => self.builder.ins().icmp(IntCC::SignedLessThanOrEqual, a, b),
-                    _ => unreachable!(),
+                if op == SK::Pow {
+                    return self.power(a, b, exponent);
                 }
+                self.combine(op, e_a.get(tree).type_(), a, b)
             }
             SK::UnaryOp => {
                 let mut iter = node.children_with_leaves(tree);
                 let op = iter.next().unwrap().into_leaf().unwrap().get(tree).kind();
-                let a = self.translate_element(tree, iter.next().unwrap());
+                let a = iter.next().unwrap();
+                let boolean = a.get(tree).type_() == &MaybeTyped::Typed(ValueType::Boolean);
+                let float = a.get(tree).type_() == &MaybeTyped::Typed(ValueType::Float);
+                let a = self.translate_element(tree, a);
                 match op {
+                    SK::Not if boolean => self.builder.ins().bxor_imm(a, 1),
+                    SK::Sub if float => {
+                        let a = self.builder.ins().bitcast(types::F64, MemFlags::new(), a);
+                        let neg = self.builder.ins().fneg(a);
+                        self.builder.ins().bitcast(self.int, MemFlags::new(), neg)
+                    }
                     SK::Not => self.builder.ins().bnot(a),
                     SK::Sub => self.builder.ins().ineg(a),
                     SK::Mul => self.builder.ins().load(self.int, MemFlags::new(), a, 0),
@@ -173,16 +595,53 @@ impl<'a> FunctionTranslator<'a> {
                 self.builder.block_params(merge_block)[0]
             }
             SK::Let => {
-                let mut iter = node.children_with_leaves(tree);
-                let ident = &self.source[iter.next().unwrap().get(tree).span()];
-                self.increase_shadowing(ident);
-                let variable = self.get(ident);
-                let new_value = self.translate_element(tree, iter.next().unwrap());
-                self.builder.def_var(variable, new_value);
+                let mut iter = node
+                    .children_with_leaves(tree)
+                    .skip_while(|e| e.get(tree).kind() == SK::MutKw);
+                let target = iter.next().unwrap();
+                let value = iter.last().unwrap();
+                let TreeElement::Node(pattern) = target else {
+                    let new_value = self.translate_stored(tree, value);
+                    let ident = &self.source[target.get(tree).span()];
+                    self.increase_shadowing(ident);
+                    let variable = self.get(ident);
+                    self.builder.def_var(variable, new_value);
+                    return self.builder.ins().iconst(self.int, 0);
+                };
+                let (bindings, fields) = match tree.node(pattern).leaves(tree) {
+                    [open, bindings @ ..] if open.kind() == SK::OpenParen => (bindings, None),
+                    [_, _, bindings @ ..] => {
+                        let ValueType::Struct(_, fields) = value.get(tree).type_().type_().unwrap()
+                        else {
+                            unreachable!()
+                        };
+                        (bindings, Some(fields))
+                    }
+                    _ => unreachable!(),
+                };
+                let new_value = self.translate_stored(tree, value);
+                for (i, binding) in bindings.iter().enumerate() {
+                    let ident = &self.source[binding.span()];
+                    let i = match fields {
+                        Some(fields) => fields.iter().position(|(f, _)| f == ident).unwrap(),
+                        None => i,
+                    };
+                    let offset = (self.int.bytes() as usize * i) as i32;
+                    let element =
+                        self.builder
+                            .ins()
+                            .load(self.int, MemFlags::new(), new_value, offset);
+                    self.increase_shadowing(ident);
+                    let variable = self.get(ident);
+                    self.builder.def_var(variable, element);
+                }
                 self.builder.ins().iconst(self.int, 0)
             }
             SK::Loop => {
-                let mut iter = node.children_with_leaves(tree);
+                let label = self.label(tree, node);
+                let mut iter = node
+                    .children_with_leaves(tree)
+                    .skip(label.is_some() as usize);
                 let a = iter.next().unwrap();
                 let b = iter.next().unwrap();
                 let c = iter.next().unwrap();
@@ -190,6 +649,7 @@ impl<'a> FunctionTranslator<'a> {
 
                 let header_block = self.builder.create_block();
                 let body_block = self.builder.create_block();
+                let step_block = self.builder.create_block();
                 let exit_block = self.builder.create_block();
 
                 self.translate_element(tree, a);
@@ -202,7 +662,12 @@ impl<'a> FunctionTranslator<'a> {
                 self.builder.switch_to_block(body_block);
                 self.builder.seal_block(body_block);
 
+                self.loops.push((label, step_block, exit_block));
                 self.translate_element(tree, d);
+                self.loops.pop();
+                self.builder.ins().jump(step_block, &[]);
+                self.builder.switch_to_block(step_block);
+                self.builder.seal_block(step_block);
                 self.translate_element(tree, c);
 
                 self.builder.ins().jump(header_block, &[]);
@@ -211,93 +676,651 @@ impl<'a> FunctionTranslator<'a> {
                 self.builder.seal_block(exit_block);
                 self.builder.ins().iconst(self.int, 0)
             }
-            // SK::Call => {
-            //     let mut iter = node.children_with_leaves(tree);
-            //     let f = self.translate_element(tree, iter.next().unwrap());
-            //     let args = iter.collect::<Vec<_>>();
-            //     let mut sig = self.module.make_signature();
-            //     for arg in &args {
-            //         let t = arg.get(tree).type_().type_().unwrap();
-            //         let t = match t {
-            //             ValueType::Number | ValueType::Pointer(_) => self.int,
-            //             _ => todo!(),
-            //         };
-            //         sig.params.push(AbiParam::new(t));
-            //     }
-
-            //     sig.returns.push(AbiParam::new(self.int));
-
-            //     let mut arg_values = Vec::new();
-            //     for arg in args {
-            //         arg_values.push(self.translate_element(tree, arg))
-            //     }
-            //     let sig_ref = self.builder.import_signature(sig);
-            //     let call = self.builder.ins().call_indirect(sig_ref, f, &arg_values);
-            //     self.builder.inst_results(call)[0]
-            // }
+            SK::While => {
+                let label = self.label(tree, node);
+                let (&b, children) = node.children().split_last().unwrap();
+                let &a = children.last().unwrap();
+
+                let header_block = self.builder.create_block();
+                let body_block = self.builder.create_block();
+                let exit_block = self.builder.create_block();
+
+                self.builder.ins().jump(header_block, &[]);
+                self.builder.switch_to_block(header_block);
+                let condition_value = self.translate_node(tree, tree.node(a));
+                self.builder
+                    .ins()
+                    .brif(condition_value, body_block, &[], exit_block, &[]);
+                self.builder.switch_to_block(body_block);
+                self.builder.seal_block(body_block);
+
+                self.loops.push((label, header_block, exit_block));
+                self.translate_node(tree, tree.node(b));
+                self.loops.pop();
+
+                self.builder.ins().jump(header_block, &[]);
+                self.builder.switch_to_block(exit_block);
+                self.builder.seal_block(header_block);
+                self.builder.seal_block(exit_block);
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::For => {
+                let label = self.label(tree, node);
+                let mut iter = node
+                    .children_with_leaves(tree)
+                    .skip(label.is_some() as usize);
+                let ident = &self.source[iter.next().unwrap().get(tree).span()];
+                let start = self.translate_element(tree, iter.next().unwrap());
+                let end = self.translate_element(tree, iter.next().unwrap());
+                let body = iter.next().unwrap();
+
+                self.enter_scope();
+                self.increase_shadowing(ident);
+                let variable = self.get(ident);
+                self.builder.def_var(variable, start);
+
+                let header_block = self.builder.create_block();
+                let body_block = self.builder.create_block();
+                let step_block = self.builder.create_block();
+                let exit_block = self.builder.create_block();
+
+                self.builder.ins().jump(header_block, &[]);
+                self.builder.switch_to_block(header_block);
+                let i = self.builder.use_var(variable);
+                let condition_value = self.builder.ins().icmp(IntCC::SignedLessThan, i, end);
+                self.builder
+                    .ins()
+                    .brif(condition_value, body_block, &[], exit_block, &[]);
+                self.builder.switch_to_block(body_block);
+                self.builder.seal_block(body_block);
+
+                self.loops.push((label, step_block, exit_block));
+                self.translate_element(tree, body);
+                self.loops.pop();
+                self.builder.ins().jump(step_block, &[]);
+                self.builder.switch_to_block(step_block);
+                self.builder.seal_block(step_block);
+                let i = self.builder.use_var(variable);
+                let i = self.builder.ins().iadd_imm(i, 1);
+                self.builder.def_var(variable, i);
+                self.exit_scope();
+
+                self.builder.ins().jump(header_block, &[]);
+                self.builder.switch_to_block(exit_block);
+                self.builder.seal_block(header_block);
+                self.builder.seal_block(exit_block);
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::BreakKw | SK::ContinueKw => {
+                let label = self.label(tree, node);
+                let &(_, continue_block, exit_block) = self
+                    .loops
+                    .iter()
+                    .rev()
+                    .find(|(l, _, _)| label.is_none() || *l == label)
+                    .unwrap();
+                let target = if node.kind() == SK::BreakKw {
+                    exit_block
+                } else {
+                    continue_block
+                };
+                self.builder.ins().jump(target, &[]);
+                let unreachable_block = self.builder.create_block();
+                self.builder.switch_to_block(unreachable_block);
+                self.builder.seal_block(unreachable_block);
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::Call | SK::MethodCall => {
+                let mut args = node
+                    .children_with_leaves(tree)
+                    .map(|arg| self.translate_element(tree, arg))
+                    .collect::<Vec<_>>();
+                if node.kind() == SK::MethodCall {
+                    // the receiver is passed as the first argument
+                    args.swap(0, 1);
+                }
+                let mut f = args.remove(0);
+                let callee = node.children_with_leaves(tree).nth(match node.kind() {
+                    SK::MethodCall => 1,
+                    _ => 0,
+                });
+                match callee.unwrap().get(tree).type_() {
+                    MaybeTyped::Typed(ValueType::Closure(_)) => {
+                        // closures are called with their captures as the first argument
+                        args.insert(0, f);
+                        f = self.builder.ins().load(self.int, MemFlags::new(), f, 0);
+                    }
+                    MaybeTyped::Typed(ValueType::FnPtr(types)) => {
+                        let params = &types[..types.len() - 1];
+                        let variadic = matches!(params.last(), Some(ValueType::Variadic(_)));
+                        let defaults = params.iter().any(|t| matches!(t, ValueType::Default(_)));
+                        let fixed = params.len() - variadic as usize;
+                        let passed = args.len().min(fixed);
+                        if defaults {
+                            // the arguments left out are computed by the function
+                            let zero = self.builder.ins().iconst(self.int, 0);
+                            args.resize(args.len().max(fixed), zero);
+                        }
+                        if variadic {
+                            // the extra arguments are passed as one record preceded by their count
+                            let extra = args.split_off(fixed);
+                            let size = self.int.bytes() * (extra.len() as u32 + 1);
+                            let slot = self.builder.create_sized_stack_slot(StackSlotData::new(
+                                StackSlotKind::ExplicitSlot,
+                                size,
+                            ));
+                            let count = self.builder.ins().iconst(self.int, extra.len() as i64);
+                            self.builder.ins().stack_store(count, slot, 0);
+                            for (i, value) in extra.into_iter().enumerate() {
+                                let offset = (self.int.bytes() as usize * (i + 1)) as i32;
+                                self.builder.ins().stack_store(value, slot, offset);
+                            }
+                            args.push(self.builder.ins().stack_addr(self.int, slot, 0));
+                        }
+                        if defaults {
+                            args.push(self.builder.ins().iconst(self.int, passed as i64));
+                        }
+                    }
+                    _ => (),
+                }
+
+                let mut sig = self.module.make_signature();
+                sig.params
+                    .extend(args.iter().map(|_| AbiParam::new(self.int)));
+                sig.returns.push(AbiParam::new(self.int));
+
+                let sig_ref = self.builder.import_signature(sig);
+                let call = self.builder.ins().call_indirect(sig_ref, f, &args);
+                self.builder.inst_results(call)[0]
+            }
             SK::ReLet => {
                 let mut iter = node.children_with_leaves(tree);
                 let a = iter.next().unwrap();
                 let op = iter.next().unwrap().get(tree).kind().op_assignment();
                 let b = iter.next().unwrap();
+                let type_ = a.get(tree).type_();
                 match a.get(tree).data().into_node().unwrap().assignable {
                     AssignLHS::Invalid => unreachable!(),
                     AssignLHS::Ident => {
                         let variable = self.get(&self.source[a.get(tree).span()]);
                         let a = self.builder.use_var(variable);
-                        let mut b = self.translate_element(tree, b);
+                        let mut b = self.translate_stored(tree, b);
                         if let Some(op) = op {
-                            b = match op {
-                                SK::Add => self.builder.ins().iadd(a, b),
-                                SK::Sub => self.builder.ins().isub(a, b),
-                                SK::Mul => self.builder.ins().imul(a, b),
-                                SK::Div => self.builder.ins().sdiv(a, b),
-                                SK::Mod => self.builder.ins().srem(a, b),
-                                SK::And => self.builder.ins().band(a, b),
-                                SK::Or => self.builder.ins().bor(a, b),
-                                SK::Xor => self.builder.ins().bxor(a, b),
-                                SK::Shl => self.builder.ins().ishl(a, b),
-                                SK::Shr => self.builder.ins().sshr(a, b),
-                                _ => unreachable!(),
-                            };
+                            b = self.combine(op, type_, a, b);
                         }
                         self.builder.def_var(variable, b);
                     }
                     AssignLHS::Deref(a) => {
-                        let a = self.translate_element(tree, a);
-                        let mut b = self.translate_element(tree, b);
-                        if let Some(op) = op {
-                            b = match op {
-                                SK::Add => self.builder.ins().iadd(a, b),
-                                SK::Sub => self.builder.ins().isub(a, b),
-                                SK::Mul => self.builder.ins().imul(a, b),
-                                SK::Div => self.builder.ins().sdiv(a, b),
-                                SK::Mod => self.builder.ins().srem(a, b),
-                                SK::And => self.builder.ins().band(a, b),
-                                SK::Or => self.builder.ins().bor(a, b),
-                                SK::Xor => self.builder.ins().bxor(a, b),
-                                SK::Shl => self.builder.ins().ishl(a, b),
-                                SK::Shr => self.builder.ins().sshr(a, b),
-                                _ => unreachable!(),
-                            };
-                        }
-                        self.builder.ins().store(MemFlags::new(), b, a, 0);
+                        let addr = self.translate_element(tree, a);
+                        let b = self.translate_stored(tree, b);
+                        self.store_at(addr, op, type_, b);
+                    }
+                    AssignLHS::Index(index) => {
+                        let addr = self.index_addr(tree, index.into_node().unwrap().get(tree));
+                        let b = self.translate_stored(tree, b);
+                        self.store_at(addr, op, type_, b);
+                    }
+                    AssignLHS::Field(access) => {
+                        let addr = self.field_addr(tree, access.into_node().unwrap().get(tree));
+                        let b = self.translate_stored(tree, b);
+                        self.store_at(addr, op, type_, b);
                     }
                 };
                 self.builder.ins().iconst(self.int, 0)
             }
+            SK::List | SK::Tuple => {
+                let elements = node.children();
+                let addr = self.alloc(elements.len());
+                for (i, &element) in elements.iter().enumerate() {
+                    let value = self.translate_stored(tree, TreeElement::Node(element));
+                    let offset = (self.int.bytes() as usize * i) as i32;
+                    self.builder
+                        .ins()
+                        .store(MemFlags::new(), value, addr, offset);
+                }
+                addr
+            }
+            SK::Struct | SK::Enum | SK::TypeAlias | SK::FileKw => {
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::StructLit => {
+                let ValueType::Struct(_, fields) =
+                    node.data().as_ref().unwrap().type_().type_().unwrap()
+                else {
+                    unreachable!()
+                };
+                let addr = self.alloc(fields.len());
+                for &field in node.children() {
+                    let mut iter = tree.node(field).children_with_leaves(tree);
+                    let name = &self.source[iter.next().unwrap().get(tree).span()];
+                    let i = fields.iter().position(|(f, _)| f == name).unwrap();
+                    let value = self.translate_stored(tree, iter.next().unwrap());
+                    let offset = (self.int.bytes() as usize * i) as i32;
+                    self.builder
+                        .ins()
+                        .store(MemFlags::new(), value, addr, offset);
+                }
+                addr
+            }
+            SK::EnumLit => {
+                let ValueType::Enum(_, variants) =
+                    node.data().as_ref().unwrap().type_().type_().unwrap()
+                else {
+                    unreachable!()
+                };
+                // the tag is followed by enough room for the largest payload
+                let payload = variants.iter().map(|(_, p)| p.len()).max().unwrap_or(0);
+                let addr = self.alloc(payload + 1);
+                let name = &self.source[node.leaves(tree)[1].span()];
+                let tag = variants.iter().position(|(v, _)| v == name).unwrap();
+                let tag = self.builder.ins().iconst(self.int, tag as i64);
+                self.builder.ins().store(MemFlags::new(), tag, addr, 0);
+                for (i, &arg) in node.children().iter().enumerate() {
+                    let value = self.translate_stored(tree, TreeElement::Node(arg));
+                    let offset = (self.int.bytes() as usize * (i + 1)) as i32;
+                    self.builder
+                        .ins()
+                        .store(MemFlags::new(), value, addr, offset);
+                }
+                addr
+            }
+            SK::Match => {
+                let mut iter = node.children_with_leaves(tree);
+                let scrutinee = self.translate_element(tree, iter.next().unwrap());
+                let merge_block = self.builder.create_block();
+                self.builder.append_block_param(merge_block, self.int);
+
+                for arm in iter {
+                    let mut iter = arm
+                        .into_node()
+                        .unwrap()
+                        .get(tree)
+                        .children_with_leaves(tree);
+                    let pattern = iter.next().unwrap().into_node().unwrap().get(tree);
+                    let arm_block = self.builder.create_block();
+                    let next_block = self.builder.create_block();
+                    let leaves = pattern.leaves(tree);
+                    match leaves {
+                        [number] if number.kind() == SK::Number => {
+                            let number = self.translate_leaf(tree, number);
+                            let cond = self.builder.ins().icmp(IntCC::Equal, scrutinee, number);
+                            self.builder
+                                .ins()
+                                .brif(cond, arm_block, &[], next_block, &[]);
+                        }
+                        [_] => {
+                            self.builder.ins().jump(arm_block, &[]);
+                        }
+                        [_, variant, ..] => {
+                            let ValueType::Enum(_, variants) =
+                                pattern.data().as_ref().unwrap().type_().type_().unwrap()
+                            else {
+                                unreachable!()
+                            };
+                            let name = &self.source[variant.span()];
+                            let tag = variants.iter().position(|(v, _)| v == name).unwrap();
+                            let found =
+                                self.builder
+                                    .ins()
+                                    .load(self.int, MemFlags::new(), scrutinee, 0);
+                            let cond = self.builder.ins().icmp_imm(IntCC::Equal, found, tag as i64);
+                            self.builder
+                                .ins()
+                                .brif(cond, arm_block, &[], next_block, &[]);
+                        }
+                        [] => unreachable!(),
+                    }
+                    self.builder.switch_to_block(arm_block);
+                    self.builder.seal_block(arm_block);
+
+                    self.enter_scope();
+                    match leaves {
+                        [number] if number.kind() == SK::Number => (),
+                        [binding] => {
+                            let ident = &self.source[binding.span()];
+                            self.increase_shadowing(ident);
+                            self.builder.def_var(self.get(ident), scrutinee);
+                        }
+                        [_, _, bindings @ ..] => {
+                            for (i, binding) in bindings.iter().enumerate() {
+                                let ident = &self.source[binding.span()];
+                                self.increase_shadowing(ident);
+                                let offset = (self.int.bytes() as usize * (i + 1)) as i32;
+                                let value = self.builder.ins().load(
+                                    self.int,
+                                    MemFlags::new(),
+                                    scrutinee,
+                                    offset,
+                                );
+                                self.builder.def_var(self.get(ident), value);
+                            }
+                        }
+                        [] => unreachable!(),
+                    }
+                    let value = self.translate_element(tree, iter.next().unwrap());
+                    self.builder.ins().jump(merge_block, &[value]);
+                    self.exit_scope();
+
+                    self.builder.switch_to_block(next_block);
+                    self.builder.seal_block(next_block);
+                }
+                // the type checker made sure one of the arms matches
+                self.builder.ins().trap(TrapCode::UnreachableCodeReached);
+                self.builder.switch_to_block(merge_block);
+                self.builder.seal_block(merge_block);
+                self.builder.block_params(merge_block)[0]
+            }
+            SK::Index => {
+                let addr = self.index_addr(tree, node);
+                self.builder.ins().load(self.int, MemFlags::new(), addr, 0)
+            }
+            SK::Access => {
+                let addr = self.field_addr(tree, node);
+                self.builder.ins().load(self.int, MemFlags::new(), addr, 0)
+            }
             SK::Pointer => {
                 let v = node.children_with_leaves(tree).next().unwrap();
-                let _v = self.translate_element(tree, v);
-                todo!()
+                let v = self.translate_element(tree, v);
+                let addr = self.alloc(1);
+                self.builder.ins().store(MemFlags::new(), v, addr, 0);
+                addr
             }
             SK::Cast => {
-                self.translate_element(tree, node.children_with_leaves(tree).nth(1).unwrap())
+                let value = node
+                    .children_with_leaves(tree)
+                    .find(|e| e.get(tree).kind() != SK::Kind)
+                    .unwrap();
+                let from = value.get(tree).type_().clone();
+                let value = self.translate_element(tree, value);
+                // casts between numbers and floats convert the value, the others keep its bits
+                match (from, node.data().as_ref().unwrap().type_()) {
+                    (MaybeTyped::Typed(ValueType::Number), MaybeTyped::Typed(ValueType::Float)) => {
+                        let float = self.builder.ins().fcvt_from_sint(types::F64, value);
+                        self.builder.ins().bitcast(self.int, MemFlags::new(), float)
+                    }
+                    (MaybeTyped::Typed(ValueType::Float), MaybeTyped::Typed(ValueType::Number)) => {
+                        let float = self
+                            .builder
+                            .ins()
+                            .bitcast(types::F64, MemFlags::new(), value);
+                        self.builder.ins().fcvt_to_sint_sat(self.int, float)
+                    }
+                    _ => value,
+                }
             }
             SK::Fx => {
-                todo!()
+                let mut iter = node.children_with_leaves(tree);
+                let name = &self.source[iter.next().unwrap().get(tree).span()];
+                let mut params = iter.collect::<Vec<_>>();
+                let body = params.pop().unwrap();
+                params.retain(|param| {
+                    !matches!(param.get(tree).kind(), SK::Ellipsis | SK::Colon | SK::Kind)
+                });
+                let defaults = params.iter().any(|p| p.get(tree).kind() == SK::Assign);
+
+                let mut ctx = self.module.make_context();
+                ctx.func.signature.params.extend(
+                    params
+                        .iter()
+                        .filter(|p| p.get(tree).kind() == SK::Identifier)
+                        .chain(defaults.then_some(&body))
+                        .map(|_| AbiParam::new(self.int)),
+                );
+                ctx.func.signature.returns.push(AbiParam::new(self.int));
+                let id = match self.declared.remove(&node.id()) {
+                    Some(id) => id,
+                    None => {
+                        let id = self
+                            .module
+                            .declare_anonymous_function(&ctx.func.signature)
+                            .unwrap();
+                        // the function is defined before its body so that it can call itself
+                        self.increase_shadowing(name);
+                        self.functions.insert(self.get_loc(name), id);
+                        id
+                    }
+                };
+
+                let mut builder_context = FunctionBuilderContext::new();
+                let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_context);
+                let entry_block = builder.create_block();
+                builder.append_block_params_for_function_params(entry_block);
+                builder.switch_to_block(entry_block);
+                builder.seal_block(entry_block);
+                let variables = (0..self.variables.len())
+                    .map(Variable::new)
+                    .inspect(|&v| builder.declare_var(v, self.int))
+                    .collect();
+
+                let mut trans = FunctionTranslator {
+                    int: self.int,
+                    source: self.source,
+                    builder,
+                    module: self.module,
+                    lookup: std::mem::take(&mut self.lookup),
+                    variables,
+                    functions: std::mem::take(&mut self.functions),
+                    definitions: std::mem::take(&mut self.definitions),
+                    declared: std::mem::take(&mut self.declared),
+                    scopes: [&self.scopes[..], &[self.entered + 1]].concat(),
+                    entered: self.entered + 1,
+                    loops: Vec::new(),
+                    recursion: None,
+                };
+                let mut values = trans.builder.block_params(entry_block).to_vec();
+                let passed = defaults.then(|| values.pop().unwrap());
+                let mut values = values.into_iter().enumerate();
+                let mut params = params.into_iter().peekable();
+                let mut variables = Vec::new();
+                while let Some(param) = params.next() {
+                    let ident = &self.source[param.get(tree).span()];
+                    let (i, mut value) = values.next().unwrap();
+                    // the default value is computed when the argument was left out
+                    if params
+                        .next_if(|p| p.get(tree).kind() == SK::Assign)
+                        .is_some()
+                    {
+                        let default_block = trans.builder.create_block();
+                        let merge_block = trans.builder.create_block();
+                        trans.builder.append_block_param(merge_block, self.int);
+                        let given = trans.builder.ins().icmp_imm(
+                            IntCC::UnsignedGreaterThan,
+                            passed.unwrap(),
+                            i as i64,
+                        );
+                        trans
+                            .builder
+                            .ins()
+                            .brif(given, merge_block, &[value], default_block, &[]);
+                        trans.builder.switch_to_block(default_block);
+                        trans.builder.seal_block(default_block);
+                        let default = trans.translate_element(tree, params.next().unwrap());
+                        trans.builder.ins().jump(merge_block, &[default]);
+                        trans.builder.switch_to_block(merge_block);
+                        trans.builder.seal_block(merge_block);
+                        value = trans.builder.block_params(merge_block)[0];
+                    }
+                    trans.increase_shadowing(ident);
+                    let variable = trans.get(ident);
+                    trans.builder.def_var(variable, value);
+                    variables.push(variable);
+                }
+                let body_block = trans.builder.create_block();
+                trans.builder.ins().jump(body_block, &[]);
+                trans.builder.switch_to_block(body_block);
+                trans.recursion = Some((id, body_block, variables));
+                let return_value = trans.translate_element(tree, body);
+                trans.builder.ins().return_(&[return_value]);
+                // the tail calls jump back to the body
+                trans.builder.seal_block(body_block);
+                trans.builder.finalize();
+
+                let FunctionTranslator {
+                    lookup,
+                    functions,
+                    definitions,
+                    declared,
+                    entered,
+                    ..
+                } = trans;
+                self.entered = entered;
+                self.lookup = lookup;
+                self.functions = functions;
+                self.definitions = definitions;
+                self.declared = declared;
+                self.definitions.push((id, ctx));
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::Lambda => {
+                let mut params = node.children_with_leaves(tree).collect::<Vec<_>>();
+                let captures = tree.node(params.pop().unwrap().into_node().unwrap());
+                let body = params.pop().unwrap();
+
+                let mut ctx = self.module.make_context();
+                ctx.func
+                    .signature
+                    .params
+                    .extend((0..=params.len()).map(|_| AbiParam::new(self.int)));
+                ctx.func.signature.returns.push(AbiParam::new(self.int));
+                let id = self
+                    .module
+                    .declare_anonymous_function(&ctx.func.signature)
+                    .unwrap();
+
+                let mut builder_context = FunctionBuilderContext::new();
+                let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_context);
+                let entry_block = builder.create_block();
+                builder.append_block_params_for_function_params(entry_block);
+                builder.switch_to_block(entry_block);
+                builder.seal_block(entry_block);
+                let variables = (0..self.variables.len())
+                    .map(Variable::new)
+                    .inspect(|&v| builder.declare_var(v, self.int))
+                    .collect();
+
+                let mut trans = FunctionTranslator {
+                    int: self.int,
+                    source: self.source,
+                    builder,
+                    module: self.module,
+                    lookup: std::mem::take(&mut self.lookup),
+                    variables,
+                    functions: std::mem::take(&mut self.functions),
+                    definitions: std::mem::take(&mut self.definitions),
+                    declared: std::mem::take(&mut self.declared),
+                    scopes: [&self.scopes[..], &[self.entered + 1]].concat(),
+                    entered: self.entered + 1,
+                    loops: Vec::new(),
+                    recursion: None,
+                };
+                // the captures are stored after the function pointer
+                let env = trans.builder.block_params(entry_block)[0];
+                for (i, capture) in captures.leaves(tree).iter().enumerate() {
+                    let variable = trans.get(&self.source[capture.span()]);
+                    let value = trans.builder.ins().load(
+                        self.int,
+                        MemFlags::new(),
+                        env,
+                        8 * (i as i32 + 1),
+                    );
+                    trans.builder.def_var(variable, value);
+                }
+                for (i, param) in params.into_iter().enumerate() {
+                    let ident =
+                        &self.source[tree.node(param.into_node().unwrap()).leaves(tree)[0].span()];
+                    trans.increase_shadowing(ident);
+                    let variable = trans.get(ident);
+                    let value = trans.builder.block_params(entry_block)[i + 1];
+                    trans.builder.def_var(variable, value);
+                }
+                let return_value = trans.translate_element(tree, body);
+                trans.builder.ins().return_(&[return_value]);
+                trans.builder.finalize();
+
+                let FunctionTranslator {
+                    lookup,
+                    functions,
+                    definitions,
+                    declared,
+                    entered,
+                    ..
+                } = trans;
+                self.entered = entered;
+                self.lookup = lookup;
+                self.functions = functions;
+                self.definitions = definitions;
+                self.declared = declared;
+                self.definitions.push((id, ctx));
+
+                let captures = captures.leaves(tree);
+                let addr = self.alloc(captures.len() + 1);
+                let f = self.module.declare_func_in_func(id, self.builder.func);
+                let f = self.builder.ins().func_addr(self.int, f);
+                self.builder.ins().store(MemFlags::new(), f, addr, 0);
+                for (i, capture) in captures.iter().enumerate() {
+                    let value = self.translate_leaf(tree, capture);
+                    self.builder
+                        .ins()
+                        .store(MemFlags::new(), value, addr, 8 * (i as i32 + 1));
+                }
+                addr
+            }
+            SK::ReturnKw => {
+                let value = tree.node(node.children()[0]);
+                match self.tail_call(tree, value) {
+                    Some((body_block, variables)) => {
+                        // the arguments are all computed before the parameters are replaced
+                        let args = value
+                            .children_with_leaves(tree)
+                            .skip(1)
+                            .map(|arg| self.translate_element(tree, arg))
+                            .collect::<Vec<_>>();
+                        for (variable, arg) in variables.into_iter().zip(args) {
+                            self.builder.def_var(variable, arg);
+                        }
+                        self.builder.ins().jump(body_block, &[]);
+                    }
+                    None => {
+                        let value = self.translate_node(tree, value);
+                        self.builder.ins().return_(&[value]);
+                    }
+                }
+                let unreachable_block = self.builder.create_block();
+                self.builder.switch_to_block(unreachable_block);
+                self.builder.seal_block(unreachable_block);
+                self.builder.ins().iconst(self.int, 0)
+            }
+            SK::AssertKw => {
+                let cond = tree.node(node.children()[0]);
+                let value = self.translate_node(tree, cond);
+                let fail_block = self.builder.create_block();
+                let pass_block = self.builder.create_block();
+                self.builder
+                    .ins()
+                    .brif(value, pass_block, &[], fail_block, &[]);
+
+                self.builder.switch_to_block(fail_block);
+                self.builder.seal_block(fail_block);
+                // the message goes to stderr, apart from what the program prints
+                let message = format!("assertion failed: {}\n", &self.source[cond.span()]);
+                let len = self.builder.ins().iconst(self.int, message.len() as i64);
+                let message = self.data(message.into_bytes().into_boxed_slice());
+                let stderr = self.builder.ins().iconst(self.int, 2);
+                self.call_libc("write", &[stderr, message, len]);
+                let code = self.builder.ins().iconst(self.int, ASSERTION_FAILED as i64);
+                self.call_libc("exit", &[code]);
+                self.builder.ins().trap(TrapCode::UnreachableCodeReached);
+
+                self.builder.switch_to_block(pass_block);
+                self.builder.seal_block(pass_block);
+                self.builder.ins().iconst(self.int, 0)
             }
             SK::Kind => self.builder.ins().iconst(self.int, 0),
+            SK::Const => {
+                let name = node.children_with_leaves(tree).next().unwrap();
+                self.increase_shadowing(&self.source[name.get(tree).span()]);
+                self.builder.ins().iconst(self.int, 0)
+            }
             s => unreachable!("{s}"),
         }
     }
@@ -305,11 +1328,29 @@ impl<'a> FunctionTranslator<'a> {
     fn translate_leaf(&mut self, _tree: &AnalyzedTree, leaf: &Leaf<LeafData>) -> Value {
         match leaf.kind() {
             SK::Identifier => {
-                let var = self.get(&self.source[leaf.span()]);
-                self.builder.use_var(var)
+                // constants are inlined
+                if let Some(LeafKind::Value(value)) = leaf.data().as_deref() {
+                    match value.value {
+                        Some(ValueData::Number(n)) => {
+                            return self.builder.ins().iconst(self.int, n as i64)
+                        }
+                        Some(ValueData::Bool(b)) => {
+                            return self.builder.ins().iconst(self.int, b as i64)
+                        }
+                        _ => (),
+                    }
+                }
+                let loc = self.get_loc(&self.source[leaf.span()]);
+                match self.functions.get(&loc) {
+                    Some(&id) => {
+                        let f = self.module.declare_func_in_func(id, self.builder.func);
+                        self.builder.ins().func_addr(self.int, f)
+                    }
+                    None => self.builder.use_var(self.variables[loc]),
+                }
             }
             SK::Stuffing => self.builder.ins().iconst(self.int, 1),
-            SK::Number | SK::Char => self.builder.ins().iconst(
+            SK::Number | SK::Float | SK::Char => self.builder.ins().iconst(
                 self.int,
                 match leaf
                     .data()
@@ -321,32 +1362,28 @@ impl<'a> FunctionTranslator<'a> {
                     .unwrap()
                 {
                     ValueData::Number(n) => *n as i64,
+                    ValueData::Float(n) => n.to_bits() as i64,
                     ValueData::Char(c) => *c as i64,
                     _ => unreachable!(),
                 },
             ),
-            SK::SemiColon => self.builder.ins().iconst(self.int, 0),
-            // SK::String => {
-            //     let data = match leaf
-            //         .data()
-            //         .as_ref()
-            //         .unwrap()
-            //         .into_value()
-            //         .value
-            //         .as_ref()
-            //         .unwrap()
-            //     {
-            //         ValueData::String(s) => s.clone().into_boxed_slice(),
-            //         _ => unreachable!(),
-            //     };
-            //     let id = self.module.declare_anonymous_data(false, false).unwrap();
-            //     let mut data_ctx = DataContext::new();
-            //     data_ctx.define(data);
-            //     self.module.define_data(id, &data_ctx).unwrap();
-            //     data_ctx.clear();
-            //     let value = self.module.declare_data_in_func(id, self.builder.func);
-            //     self.builder.ins().global_value(types::I64, value)
-            // }
+            SK::SemiColon | SK::FalseKw => self.builder.ins().iconst(self.int, 0),
+            SK::TrueKw => self.builder.ins().iconst(self.int, 1),
+            SK::String => {
+                let data = match leaf
+                    .data()
+                    .as_ref()
+                    .unwrap()
+                    .into_value()
+                    .value
+                    .as_ref()
+                    .unwrap()
+                {
+                    ValueData::String(s) => s.clone().into_boxed_slice(),
+                    _ => unreachable!(),
+                };
+                self.data(data)
+            }
             s => unreachable!("{s}"),
         }
     }
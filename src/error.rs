@@ -27,6 +27,12 @@ pub enum ErrorKind {
         operation: SyntaxKind,
     },
     ByteOverflow,
+    InvalidEscape,
+    MisplacedSeparator,
+    InvalidDigit {
+        radix: u32,
+    },
+    NoDigits,
     Custom {
         message: String,
     },
@@ -34,25 +40,100 @@ pub enum ErrorKind {
         type_: ValueType,
     },
     UndefinedReference,
+    UsedBeforeDeclaration {
+        name: String,
+    },
+    DeclaredLater,
+    AlreadyDefined,
+    FirstDefinition,
+    MissingField {
+        field: String,
+    },
     UnknownType,
+    CyclicType,
+    CyclicAlias {
+        chain: Vec<String>,
+    },
+    CyclicDependency,
     InvalidLHS,
+    NonExhaustiveMatch,
+    RepeatedArm,
+    ArmAfterCatchAll,
+    OutOfBounds {
+        index: i64,
+        len: usize,
+    },
     KeywordMisuse {
         keyword: SyntaxKind,
     },
+    UnknownLabel {
+        label: String,
+    },
     TooManyArgs {
         expected: usize,
         found: usize,
     },
+    TooFewArgs {
+        expected: usize,
+        found: usize,
+    },
+    DefaultBeforeRequired,
+    NamedArgsUnsupported,
+    PositionalAfterNamed,
+    UnknownArg,
+    DuplicateArg,
+    MissingArg {
+        name: String,
+    },
+    NonConstantDefault,
+    NotConstant,
+    AssertionFails,
+    ImmutableAssignment {
+        name: String,
+    },
+    DeclaredImmutable,
+    OuterVariable {
+        name: String,
+    },
+    DefinedOutside,
     WrongType {
         expected: ValueType,
         found: ValueType,
     },
     NotCallable,
+    InvalidCast {
+        from: ValueType,
+        to: ValueType,
+    },
+    DivisionByZero,
+    ConstantOverflow,
+    UnusedVariable {
+        name: String,
+    },
+    UnreachableCode,
+    ConstantCondition {
+        value: bool,
+    },
+    UnreadableFile(std::io::ErrorKind),
+    CyclicFile {
+        chain: Vec<String>,
+    },
     CraneliftError(ModuleError),
 }
 
+/// Error returned when compiling a program
+#[derive(Debug)]
+pub enum CompileError<'source> {
+    /// The source file could not be read
+    Io(std::io::Error),
+    /// Errors found while parsing and analyzing the source
+    Analysis(Vec<Error<'source>>),
+    /// Cranelift failed to generate the code
+    Codegen(Box<Error<'source>>),
+}
+
 /// Location of the error
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Location {
     line: Span,
     column: Span,
@@ -85,7 +166,8 @@ impl Location {
             column.start = source.lines().last().unwrap_or_default().len();
         }
 
-        if column.is_empty() {
+        // a span ending on a later line can end at a column before its start
+        if line.start >= line.end && column.is_empty() {
             column.end = column.start + 1;
         }
         Self { column, line }
@@ -97,7 +179,7 @@ impl Location {
 pub enum ErrorVariant {
     Error,
     Warning,
-    // Help,
+    Help,
 }
 
 impl<'source> Error<'source> {
@@ -126,6 +208,24 @@ impl<'source> Error<'source> {
         }
     }
 
+    /// Instantiate a help, pointing at what can be changed to fix an error
+    pub fn help(kind: ErrorKind, span: Span, source: &'source str) -> Error<'source> {
+        Self {
+            variant: ErrorVariant::Help,
+            location: Location::from_span(span, source),
+            source,
+            kind,
+            path: String::from("<program>"),
+            note: "Copium",
+        }
+    }
+
+    /// Turns a warning into an error
+    pub fn deny(mut self) -> Self {
+        self.variant = ErrorVariant::Error;
+        self
+    }
+
     pub fn with_path(mut self, path: String) -> Self {
         self.path = path;
         self
@@ -135,6 +235,19 @@ impl<'source> Error<'source> {
         self.path = path
     }
 
+    /// Line of the source where the error starts
+    pub(crate) fn line(&self) -> usize {
+        self.location.line.start
+    }
+
+    /// Moves the error into the part of its source starting at the given line
+    pub(crate) fn relocate(&mut self, line: usize, source: &'source str, path: String) {
+        self.location.line.start -= line;
+        self.location.line.end -= line;
+        self.source = source;
+        self.path = path;
+    }
+
     pub fn with_note(mut self, note: &'static str) -> Self {
         self.note = note;
         self
@@ -145,13 +258,60 @@ impl<'source> Error<'source> {
     }
 }
 
+/// Sorts the errors by where they start in the source, the helps staying after their error
+pub(crate) fn sort(errors: &mut Vec<Error<'_>>) {
+    let mut groups: Vec<Vec<Error>> = Vec::new();
+    for error in errors.drain(..) {
+        match (&error.variant, groups.last_mut()) {
+            (ErrorVariant::Help, Some(group)) => group.push(error),
+            _ => groups.push(vec![error]),
+        }
+    }
+    groups.sort_by_key(|group| (group[0].location.line.start, group[0].location.column.start));
+    errors.extend(groups.into_iter().flatten());
+}
+
+/// Removes the cyclic type errors going through the declaration at the span, with their helps. They
+/// follow from a use of the declared name before it, which is reported instead
+pub(crate) fn retract_cycles(errors: &mut Vec<Error<'_>>, declaration: Span, source: &str) {
+    let declaration = Location::from_span(declaration, source);
+    let mut i = 0;
+    while i < errors.len() {
+        let len = 1 + errors[i + 1..]
+            .iter()
+            .take_while(|e| matches!(e.variant, ErrorVariant::Help))
+            .count();
+        let group = &errors[i..i + len];
+        if matches!(group[0].kind, ErrorKind::CyclicType)
+            && group.iter().any(|e| e.location == declaration)
+        {
+            errors.drain(i..i + len);
+        } else {
+            i += len;
+        }
+    }
+}
+
 impl Display for Error<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let (text, color) = match self.variant {
             ErrorVariant::Error => ("error", Color::RED),
             ErrorVariant::Warning => ("warning", Color::ORANGE),
+            ErrorVariant::Help => ("help", Color::BLUE),
         };
 
+        // `{:#}` gives a single uncolored line for tools reading the errors
+        if f.alternate() {
+            return write!(
+                f,
+                "{}:{}:{}: {text}: {}",
+                self.path,
+                self.location.line.start + 1,
+                self.location.column.start + 1,
+                self.kind
+            );
+        }
+
         let start_line = self.source.lines().nth(self.location.line.start).unwrap();
         let blue = Color::BLUE;
 
@@ -183,10 +343,14 @@ impl Display for Error<'_> {
                      {: >3} |\x1b[39m {}\n\
                      \x1b[{blue}m    |\x1b[{color}m {}^\x1b[39m",
                 " ".repeat(self.location.column.start),
-                "_".repeat(start_line.len() - 1),
+                "_".repeat(
+                    start_line
+                        .len()
+                        .saturating_sub(self.location.column.start + 1)
+                ),
                 self.location.line.end + 1,
                 self.source.lines().nth(self.location.line.end).unwrap(),
-                "_".repeat(self.location.column.end - 1),
+                "_".repeat(self.location.column.end.saturating_sub(1)),
             )?,
 
             _ => writeln!(
@@ -198,10 +362,14 @@ impl Display for Error<'_> {
                      {: >3} |\x1b[39m {}\n\
                      \x1b[{blue}m    |\x1b[{color}m {}^\x1b[39m",
                 " ".repeat(self.location.column.start),
-                "_".repeat(start_line.len() - 1),
+                "_".repeat(
+                    start_line
+                        .len()
+                        .saturating_sub(self.location.column.start + 1)
+                ),
                 self.location.line.end + 1,
                 self.source.lines().nth(self.location.line.end).unwrap(),
-                "_".repeat(self.location.column.end - 1),
+                "_".repeat(self.location.column.end.saturating_sub(1)),
             )?,
         }
         writeln!(f, "\x1b[{}m    |\n    =\x1b[39m {}", Color::BLUE, self.note)?;
@@ -229,24 +397,90 @@ impl Display for ErrorKind {
             ErrorKind::ByteOverflow => {
                 write!(f, "This shit is too big to fit in a byte")
             }
+            ErrorKind::InvalidEscape => {
+                write!(f, "I don't know this escape sequence")
+            }
+            ErrorKind::MisplacedSeparator => {
+                write!(f, "An underscore can only be put between two digits")
+            }
+            ErrorKind::InvalidDigit { radix } => {
+                write!(f, "This is not a digit of a base {radix} number")
+            }
+            ErrorKind::NoDigits => {
+                write!(f, "This number has no digits after its prefix")
+            }
             ErrorKind::TypeError { type_ } => {
                 write!(f, "Did not expect to see a `{type_}` there")
             }
             ErrorKind::UndefinedReference => {
                 write!(f, "This thing is not defined")
             }
+            ErrorKind::UsedBeforeDeclaration { name } => {
+                write!(f, "`{name}` is used before it is declared")
+            }
+            ErrorKind::DeclaredLater => {
+                write!(f, "It is declared here, after its use")
+            }
+            ErrorKind::AlreadyDefined => {
+                write!(f, "This thing is already defined")
+            }
+            ErrorKind::FirstDefinition => {
+                write!(f, "It was first defined here")
+            }
+            ErrorKind::MissingField { field } => {
+                write!(f, "The field `{field}` is missing here")
+            }
             ErrorKind::UnknownType => {
                 write!(f, "The type of this thing is unknown")
             }
+            ErrorKind::CyclicType => {
+                write!(
+                    f,
+                    "The type of this thing can't be inferred, it depends on itself"
+                )
+            }
+            ErrorKind::CyclicDependency => {
+                write!(f, "It depends on itself through this")
+            }
             ErrorKind::InvalidLHS => {
                 write!(f, "The left-hand side of the expression is unacceptable")
             }
+            ErrorKind::NonExhaustiveMatch => {
+                write!(f, "This match does not handle every possible value")
+            }
+            ErrorKind::RepeatedArm => {
+                write!(
+                    f,
+                    "This arm is never taken, one before it matches the same value"
+                )
+            }
+            ErrorKind::ArmAfterCatchAll => {
+                write!(
+                    f,
+                    "This arm is never taken, one before it matches every value"
+                )
+            }
+            ErrorKind::OutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "Index {index} is out of bounds for an array of length {len}"
+                )
+            }
             ErrorKind::NotCallable => {
                 write!(f, "This thing isn't callable")
             }
+            ErrorKind::DivisionByZero => {
+                write!(f, "This divides by zero")
+            }
+            ErrorKind::ConstantOverflow => {
+                write!(f, "This overflows a byte")
+            }
             ErrorKind::KeywordMisuse { keyword } => {
                 write!(f, "{keyword} can not be used here")
             }
+            ErrorKind::UnknownLabel { label } => {
+                write!(f, "There is no enclosing loop named `{label}`")
+            }
             ErrorKind::TooManyArgs { expected, found } => {
                 write!(
                     f,
@@ -254,6 +488,70 @@ impl Display for ErrorKind {
                     expected, found
                 )
             }
+            ErrorKind::TooFewArgs { expected, found } => {
+                write!(
+                    f,
+                    "The function expects at least {} arguments but {} values were passed in",
+                    expected, found
+                )
+            }
+            ErrorKind::DefaultBeforeRequired => {
+                write!(
+                    f,
+                    "Parameters with a default value must come after the required ones"
+                )
+            }
+            ErrorKind::NamedArgsUnsupported => {
+                write!(
+                    f,
+                    "Only functions called by their name can be given arguments by name"
+                )
+            }
+            ErrorKind::PositionalAfterNamed => {
+                write!(f, "This argument can not follow arguments given by name")
+            }
+            ErrorKind::UnknownArg => {
+                write!(f, "The function has no parameter with this name")
+            }
+            ErrorKind::DuplicateArg => {
+                write!(f, "This argument was already given")
+            }
+            ErrorKind::MissingArg { name } => {
+                write!(
+                    f,
+                    "The argument `{name}` is left out before arguments that are given"
+                )
+            }
+            ErrorKind::NotConstant => {
+                write!(f, "The value of a constant must be known at compile time")
+            }
+            ErrorKind::AssertionFails => {
+                write!(f, "This assertion is always false")
+            }
+            ErrorKind::ImmutableAssignment { name } => {
+                write!(
+                    f,
+                    "`{name}` can't be assigned, it was declared without `mut`"
+                )
+            }
+            ErrorKind::DeclaredImmutable => {
+                write!(f, "Declaring it with `let mut` would make it assignable")
+            }
+            ErrorKind::OuterVariable { name } => {
+                write!(
+                    f,
+                    "`{name}` is defined outside of this function, which can't use it"
+                )
+            }
+            ErrorKind::DefinedOutside => {
+                write!(f, "It is defined here, a lambda could capture it")
+            }
+            ErrorKind::NonConstantDefault => {
+                write!(
+                    f,
+                    "A default value can only use constants and the parameters before it"
+                )
+            }
             ErrorKind::WrongType { expected, found } => {
                 write!(
                     f,
@@ -261,6 +559,35 @@ impl Display for ErrorKind {
                     expected, found
                 )
             }
+            ErrorKind::InvalidCast { from, to } => {
+                write!(f, "A `{from}` can't be cast to a `{to}`")
+            }
+            ErrorKind::UnusedVariable { name } => {
+                write!(f, "`{name}` is never used")
+            }
+            ErrorKind::UnreachableCode => {
+                write!(f, "This code is never reached")
+            }
+            ErrorKind::ConstantCondition { value } => {
+                write!(f, "This condition is always {value}")
+            }
+            ErrorKind::UnreadableFile(kind) => {
+                write!(f, "This file could not be read: {kind}")
+            }
+            ErrorKind::CyclicAlias { chain } => {
+                write!(
+                    f,
+                    "This type alias ends up referring to itself: {}",
+                    chain.join(" -> ")
+                )
+            }
+            ErrorKind::CyclicFile { chain } => {
+                write!(
+                    f,
+                    "This file ends up including the file including it: {}",
+                    chain.join(" -> ")
+                )
+            }
             ErrorKind::CraneliftError(error) => {
                 write!(f, "{error}")
             }
@@ -268,6 +595,45 @@ impl Display for ErrorKind {
     }
 }
 
+impl Display for CompileError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileError::Io(err) => write!(f, "{err}"),
+            CompileError::Analysis(errors) if f.alternate() => {
+                for err in errors {
+                    writeln!(f, "{err:#}")?;
+                }
+                Ok(())
+            }
+            CompileError::Analysis(errors) => {
+                for err in errors {
+                    writeln!(f, "{err}")?;
+                }
+                Ok(())
+            }
+            CompileError::Codegen(err) if f.alternate() => write!(f, "{err:#}"),
+            CompileError::Codegen(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error<'_> {}
+
+impl std::error::Error for CompileError<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompileError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CompileError<'_> {
+    fn from(err: std::io::Error) -> Self {
+        CompileError::Io(err)
+    }
+}
+
 /// **Only works in ANSI supported terminals**
 struct Color(u8, u8, u8);
 
@@ -4,47 +4,321 @@ pub mod error;
 pub mod parser;
 mod tree;
 
-use std::io::Write;
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use analyzer::{builder::Analyzer, type_checker::TypeChecker};
 use compiler::JIT;
-use parser::Parser;
+use cranelift_module::ModuleError;
+use logos::Logos;
+use parser::{
+    syntax::{ParseResult, SyntaxKind},
+    Parser,
+};
 
 pub type Span = core::ops::Range<usize>;
 
-use error::{Error, ErrorKind};
+use error::{CompileError, Error, ErrorKind};
 
-use crate::analyzer::AnalysisResult;
+use crate::analyzer::{value::ValueType, AnalysisResult, Analyzed, AnalyzedTree};
 
-pub fn compile(file: String, source: &str) -> Result<i64, Vec<Error>> {
-    let parsed = Parser::new(source).parse();
-    // println!("{:?}\n", parsed.parse);
+/// Source of a program, made of a file and the files it includes with `file "path"`. The included
+/// files come before the file including them and each of them is only included once
+pub struct Program {
+    source: String,
+    /// Files making up the source with the range they take in it
+    files: Vec<(String, Span)>,
+    /// Included files that could not be included
+    errors: Vec<(Span, IncludeError)>,
+    /// Whether the warnings keep the program from compiling
+    deny_warnings: bool,
+}
+
+/// Why a file could not be included
+enum IncludeError {
+    Unreadable(std::io::ErrorKind),
+    /// Files including each other, from the one included again to itself
+    Cyclic(Vec<String>),
+}
+
+impl Program {
+    /// Reads the file at the path and the files it includes, failing with `CompileError::Io` if
+    /// the file can't be read
+    pub fn load(path: impl AsRef<Path>) -> Result<Program, CompileError<'static>> {
+        let source = fs::read_to_string(path.as_ref())?;
+        Ok(Program::new(path, source))
+    }
+
+    /// Program of the source of the file at the path, the files it includes are looked up
+    /// relative to it
+    pub fn new(path: impl AsRef<Path>, source: String) -> Program {
+        Program::with_reader(path, source, |path| fs::read_to_string(path))
+    }
+
+    /// Program of the source of the file at the path, the files it includes are looked up
+    /// relative to it and read with the given function instead of from the file system
+    pub fn with_reader(
+        path: impl AsRef<Path>,
+        source: String,
+        mut read: impl FnMut(&Path) -> std::io::Result<String>,
+    ) -> Program {
+        let mut program = Program {
+            source: String::new(),
+            files: Vec::new(),
+            errors: Vec::new(),
+            deny_warnings: false,
+        };
+        program.include(
+            path.as_ref(),
+            source,
+            &mut read,
+            &mut Vec::new(),
+            &mut HashSet::new(),
+        );
+        program
+    }
+
+    /// Makes the warnings errors, so that the program fails to compile if it has any
+    pub fn set_deny_warnings(&mut self, deny: bool) {
+        self.deny_warnings = deny;
+    }
+
+    /// Appends the files included by the source and then the source itself, `including` is the
+    /// chain of files being included down to this one
+    fn include(
+        &mut self,
+        path: &Path,
+        source: String,
+        read: &mut impl FnMut(&Path) -> std::io::Result<String>,
+        including: &mut Vec<(PathBuf, String)>,
+        included: &mut HashSet<PathBuf>,
+    ) {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        including.push((key.clone(), path.display().to_string()));
+        let mut errors = Vec::new();
+        let mut tokens = SyntaxKind::lexer(&source).spanned();
+        while let Some((token, _)) = tokens.next() {
+            if token != Ok(SyntaxKind::FileKw) {
+                continue;
+            }
+            let Some((Ok(SyntaxKind::String), span)) = tokens.next() else {
+                continue;
+            };
+            let file = path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(&source[span.start + 1..span.end - 1]);
+            let key = file.canonicalize().unwrap_or_else(|_| file.clone());
+            if let Some(i) = including.iter().position(|(k, _)| k == &key) {
+                let mut chain = including[i..]
+                    .iter()
+                    .map(|(_, path)| path.clone())
+                    .collect::<Vec<_>>();
+                chain.push(file.display().to_string());
+                errors.push((span, IncludeError::Cyclic(chain)));
+            } else if !included.contains(&key) {
+                match read(&file) {
+                    Ok(source) => self.include(&file, source, read, including, included),
+                    Err(err) => errors.push((span, IncludeError::Unreadable(err.kind()))),
+                }
+            }
+        }
+        including.pop();
+        included.insert(key);
+
+        let start = self.source.len();
+        self.source.push_str(&source);
+        // the files are joined by whole lines so that the errors can be moved back to them
+        if !source.ends_with('\n') {
+            self.source.push('\n');
+        }
+        self.errors.extend(
+            errors
+                .into_iter()
+                .map(|(span, err)| (span.start + start..span.end + start, err)),
+        );
+        self.files
+            .push((path.display().to_string(), start..self.source.len()));
+    }
+
+    /// Points the errors at the files they were found in
+    fn locate<'a>(&'a self, errors: &mut [Error<'a>]) {
+        for error in errors {
+            let line = error.line();
+            let mut start_line = 0;
+            for (path, span) in &self.files {
+                let source = &self.source[span.clone()];
+                let lines = source.lines().count();
+                if line < start_line + lines || span.end == self.source.len() {
+                    error.relocate(start_line, source, path.clone());
+                    break;
+                }
+                start_line += lines;
+            }
+        }
+    }
+}
+
+/// Value of the last statement of a program that was run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Output {
+    Number(i64),
+    Float(f64),
+}
+
+impl Display for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Output::Number(n) => write!(f, "{n}"),
+            Output::Float(n) => write!(f, "{n:?}"),
+        }
+    }
+}
+
+/// Compiles and runs the program, returning the value of its last statement with the warnings of
+/// the program
+pub fn compile(program: &Program) -> Result<(Output, Vec<Error<'_>>), CompileError<'_>> {
+    let (analyzed, warnings) = analyze(program)?;
+    let float = result_type(&analyzed) == Some(ValueType::Float);
+    let mut jit = JIT::new(&program.source);
+    match jit.compile(analyzed) {
+        Err(err) => Err(cranelift_error(err, program)),
+        Ok(code) => Ok({
+            let res = code();
+            compiler::flush();
+            // floats are returned with their bits in an integer
+            let res = if float {
+                Output::Float(f64::from_bits(res as u64))
+            } else {
+                Output::Number(res)
+            };
+            (res, warnings)
+        }),
+    }
+}
+
+/// Type of the last statement of the program, which is the value it returns
+fn result_type(analyzed: &Analyzed) -> Option<ValueType> {
+    let tree = &analyzed.tree;
+    let mut last = tree.node(AnalyzedTree::ROOT);
+    loop {
+        let id = match last.kind() {
+            SyntaxKind::Root | SyntaxKind::Statement | SyntaxKind::Scope => {
+                *last.children().last()?
+            }
+            // the type checker makes sure the other branch agrees
+            SyntaxKind::If | SyntaxKind::Ternary if last.children().len() == 3 => {
+                last.children()[1]
+            }
+            _ => break,
+        };
+        last = tree.node(id);
+    }
+    last.data().as_ref()?.type_().type_().cloned()
+}
+
+/// Returns the Cranelift IR generated for the program instead of running it, with the warnings of
+/// the program
+pub fn emit_ir(program: &Program) -> Result<(String, Vec<Error<'_>>), CompileError<'_>> {
+    let (analyzed, warnings) = analyze(program)?;
+    JIT::new(&program.source)
+        .ir(analyzed)
+        .map(|ir| (ir, warnings))
+        .map_err(|err| cranelift_error(err, program))
+}
+
+/// Returns the tokens of the program, one per line with their span in the program, failing with
+/// the parts of the program that are not tokens
+pub fn emit_tokens(program: &Program) -> Result<String, CompileError<'_>> {
+    let tokens = parser::lex(&program.source).map_err(|mut errors| {
+        program.locate(&mut errors);
+        CompileError::Analysis(errors)
+    })?;
+    Ok(tokens
+        .into_iter()
+        .map(|token| {
+            format!(
+                "{} {}..{} {:?}\n",
+                token.kind,
+                token.span.start,
+                token.span.end,
+                &program.source[token.span.clone()]
+            )
+        })
+        .collect())
+}
+
+/// Returns the parse tree of the program
+pub fn emit_ast(program: &Program) -> Result<String, CompileError<'_>> {
+    parse(program).map(|parsed| format!("{:?}", parsed.parse))
+}
+
+/// Returns the program printed back from its parse tree, with included files inlined
+pub fn emit_source(program: &Program) -> Result<String, CompileError<'_>> {
+    parse(program).map(|parsed| parser::printer::to_source(&parsed.parse, &program.source))
+}
+
+/// Parses the program, failing if any of its files could not be included
+fn parse(program: &Program) -> Result<ParseResult<'_>, CompileError<'_>> {
+    let source = program.source.as_str();
+    let mut errors = program
+        .errors
+        .iter()
+        .map(|(span, err)| {
+            let kind = match err {
+                IncludeError::Unreadable(kind) => ErrorKind::UnreadableFile(*kind),
+                IncludeError::Cyclic(chain) => ErrorKind::CyclicFile {
+                    chain: chain.clone(),
+                },
+            };
+            Error::error(kind, span.clone(), source)
+        })
+        .collect::<Vec<_>>();
+    let mut parsed = Parser::new(source).parse();
+    if errors.is_empty() && parsed.errors.is_empty() {
+        Ok(parsed)
+    } else {
+        errors.append(&mut parsed.errors);
+        error::sort(&mut errors);
+        program.locate(&mut errors);
+        Err(CompileError::Analysis(errors))
+    }
+}
+
+/// Parses and type checks the program, returning it with its warnings
+fn analyze(program: &Program) -> Result<(Analyzed<'_>, Vec<Error<'_>>), CompileError<'_>> {
+    let source = program.source.as_str();
+    // the analysis relies on a well formed tree
+    let parsed = parse(program)?;
     let analyzed = Analyzer::new(source, parsed).analyze();
-    // println!("{}\n", analyzed.analyzed.tree);
     let AnalysisResult {
         mut errors,
+        mut warnings,
         analyzed,
     } = TypeChecker::new(source, analyzed).analyze();
-    println!("{}\n", analyzed.tree);
+    if program.deny_warnings {
+        errors.extend(warnings.drain(..).map(Error::deny));
+    }
     if errors.is_empty() {
-        let mut jit = JIT::new(source);
-        match jit.compile(analyzed) {
-            Err(err) => Err(vec![Error::error(
-                ErrorKind::CraneliftError(err),
-                0..source.len(),
-                source,
-            )
-            .with_path(file)]),
-            Ok(code) => Ok({
-                let res = code();
-                std::io::stdout().flush().unwrap();
-                res
-            }),
-        }
+        error::sort(&mut warnings);
+        program.locate(&mut warnings);
+        Ok((analyzed, warnings))
     } else {
-        for error in &mut errors {
-            error.set_path(file.clone());
-        }
-        Err(errors)
+        error::sort(&mut errors);
+        program.locate(&mut errors);
+        Err(CompileError::Analysis(errors))
     }
 }
+
+fn cranelift_error(err: ModuleError, program: &Program) -> CompileError<'_> {
+    let (path, span) = program.files.last().unwrap();
+    let source = &program.source[span.clone()];
+    CompileError::Codegen(Box::new(
+        Error::error(ErrorKind::CraneliftError(err), 0..source.len(), source)
+            .with_path(path.clone()),
+    ))
+}
@@ -1,19 +1,108 @@
-use std::fs;
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let contents = fs::read_to_string("test.snt")?;
-    match snotty::compile("test.snt".to_string(), &contents) {
-        Err(errors) => {
-            for err in errors {
-                eprintln!("{}\n", err)
+use std::{fs, process::ExitCode};
+
+use snotty::{error::Error, Program};
+
+const USAGE: &str =
+    "usage: snotty <input.snt> [-o <output>] [--emit tokens|ast|source|ir|run] [--deny-warnings]";
+
+/// Exit code when the program fails to compile
+const COMPILE_ERROR: u8 = 1;
+/// Exit code when the arguments are wrong
+const USAGE_ERROR: u8 = 2;
+/// Exit code when a file can't be read or written
+const IO_ERROR: u8 = 3;
+// a program stopped by a failing `assert` exits with `compiler::ASSERTION_FAILED`
+
+/// What to output for the input
+enum Emit {
+    Tokens,
+    Ast,
+    /// The source printed back from the parse tree
+    Source,
+    Ir,
+    /// Runs the program and outputs the value of its last statement
+    Run,
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(USAGE_ERROR)
+}
+
+/// Prints the warnings of a compiled program, they don't keep it from being output
+fn warn<T>((output, warnings): (T, Vec<Error>)) -> T {
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+    output
+}
+
+fn main() -> ExitCode {
+    let mut input = None;
+    let mut output = None;
+    let mut emit = Emit::Run;
+    let mut deny_warnings = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => match args.next() {
+                Some(path) => output = Some(path),
+                None => return usage(),
+            },
+            "--emit" => {
+                emit = match args.next().as_deref() {
+                    Some("tokens") => Emit::Tokens,
+                    Some("ast") => Emit::Ast,
+                    Some("source") => Emit::Source,
+                    Some("ir") => Emit::Ir,
+                    Some("run") => Emit::Run,
+                    _ => return usage(),
+                }
+            }
+            "--deny-warnings" => deny_warnings = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
             }
+            _ if arg.starts_with('-') || input.is_some() => return usage(),
+            _ => input = Some(arg),
         }
-        Ok(res) => {
-            println!(
-                "\n----------------------\nCode executed with output: {}",
-                res
-            );
+    }
+    let Some(input) = input else {
+        return usage();
+    };
+
+    let mut program = match Program::load(&input) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("{input}: {err}");
+            return ExitCode::from(IO_ERROR);
+        }
+    };
+    program.set_deny_warnings(deny_warnings);
+    let result = match emit {
+        Emit::Tokens => snotty::emit_tokens(&program),
+        Emit::Ast => snotty::emit_ast(&program),
+        Emit::Source => snotty::emit_source(&program),
+        Emit::Ir => snotty::emit_ir(&program).map(warn),
+        Emit::Run => snotty::compile(&program).map(|res| format!("{}\n", warn(res))),
+    };
+    let text = match result {
+        Ok(text) => text,
+        Err(err) => {
+            eprint!("{err}");
+            return ExitCode::from(COMPILE_ERROR);
+        }
+    };
+
+    match output {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, text) {
+                eprintln!("{path}: {err}");
+                return ExitCode::from(IO_ERROR);
+            }
         }
+        None => print!("{text}"),
     }
-    Ok(())
+    ExitCode::SUCCESS
 }
@@ -1,16 +1,32 @@
+pub mod printer;
 pub mod syntax;
 
-use std::iter::Peekable;
-
 use crate::{
     error::{Error, ErrorKind},
     Span,
 };
 
-use syntax::{ParseResult, ParseTreeBuilder, SyntaxKind};
+use syntax::{ParseResult, ParseTreeBuilder, SyntaxKind, Token};
 use SyntaxKind::*;
 
-use logos::{Logos, SpannedIter};
+use logos::Logos;
+
+/// Splits the source into its tokens, failing with the parts of it that are not tokens
+pub fn lex(source: &str) -> Result<Vec<Token>, Vec<Error<'_>>> {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for (kind, span) in SyntaxKind::lexer(source).spanned() {
+        match kind {
+            Ok(kind) => tokens.push(Token { kind, span }),
+            Err(()) => errors.push(Error::error(ErrorKind::UnknownSyntax, span, source)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum ParseRecovery {
@@ -39,31 +55,37 @@ pub struct Parser<'a> {
     p_loc: usize,
     s_loc: usize,
     e_loc: usize,
-    tokens: Peekable<SpannedIter<'a, SyntaxKind>>,
+    tokens: Vec<(Result<SyntaxKind, ()>, Span)>,
+    /// Index of the current token in self.tokens
+    cursor: usize,
     builder: ParseTreeBuilder,
     errors: Vec<Error<'a>>,
     recovery: Vec<SyntaxKind>,
+    /// Whether `S {}` is an empty struct literal rather than a value followed by a block
+    empty_struct: bool,
 }
 
 impl<'a> Parser<'a> {
     /// Creates a new parser for the given source input
-    pub fn new(source: &'a str) -> Parser {
-        let mut tokens = SyntaxKind::lexer(source).spanned().peekable();
+    pub fn new(source: &'a str) -> Parser<'a> {
+        let tokens = SyntaxKind::lexer(source).spanned().collect::<Vec<_>>();
         Self {
             source,
             p_loc: 0,
             s_loc: tokens
-                .peek()
+                .first()
                 .map(|&(_, Span { start, .. })| start)
                 .unwrap_or(0),
             e_loc: tokens
-                .peek()
+                .first()
                 .map(|&(_, Span { end, .. })| end)
                 .unwrap_or(source.len()),
             tokens,
+            cursor: 0,
             builder: ParseTreeBuilder::new(),
             errors: Vec::new(),
             recovery: Vec::new(),
+            empty_struct: true,
         }
     }
 
@@ -72,8 +94,15 @@ impl<'a> Parser<'a> {
         self.builder.start_node(Root, 0);
 
         while self.current_syntax() != Eof {
+            // a statement that failed to parse stops at the next semicolon
             let s = self.statement();
-            assert!(matches!(s, ParseRecovery::Ok | ParseRecovery::Eof), "{s:?}");
+            assert!(
+                matches!(
+                    s,
+                    ParseRecovery::Ok | ParseRecovery::Eof | ParseRecovery::SemiColon
+                ),
+                "{s:?}"
+            );
         }
 
         assert_eq!(self.current_syntax(), Eof);
@@ -86,7 +115,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parses statements: Let, Return, File, Scope, Loop, If, Function
+    /// Parses statements: Let, Return, Break, Continue, File, Scope, Loop, While, If, Function, Struct
     fn statement(&mut self) -> ParseRecovery {
         self.builder.start_node(Statement, self.s_loc);
         let s = match self.current_syntax() {
@@ -94,9 +123,48 @@ impl<'a> Parser<'a> {
                 self.builder.start_node(Let, self.s_loc);
                 self.recovery.push(Assign);
                 self.pass();
+                if self.current_syntax() == MutKw {
+                    self.bump();
+                }
+                let s = match self.current_syntax() {
+                    OpenParen => self.expect_func(Self::destructure, 1, 2),
+                    Identifier if self.nth_syntax(1) == OpenBrace => {
+                        self.expect_func(Self::destructure, 1, 2)
+                    }
+                    _ => self.expect(&[Identifier], 1, 2, Some(true)),
+                };
+                if let ParseAction::Return(s) = s {
+                    return s;
+                }
+                if self.current_syntax() == Colon {
+                    self.pass();
+                    if let ParseAction::Return(s) = self.expect_func(Self::kind, 1, 2) {
+                        return s;
+                    }
+                }
+
+                if let ParseAction::Return(s) = self.expect(&[Assign], 1, 2, Some(false)) {
+                    return s;
+                }
+                self.recovery.pop();
+
+                let s = self.expression();
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            ConstKw => {
+                self.builder.start_node(Const, self.s_loc);
+                self.recovery.push(Assign);
+                self.pass();
                 if let ParseAction::Return(s) = self.expect(&[Identifier], 1, 2, Some(true)) {
                     return s;
                 }
+                if self.current_syntax() == Colon {
+                    self.pass();
+                    if let ParseAction::Return(s) = self.expect_func(Self::kind, 1, 2) {
+                        return s;
+                    }
+                }
 
                 if let ParseAction::Return(s) = self.expect(&[Assign], 1, 2, Some(false)) {
                     return s;
@@ -104,16 +172,25 @@ impl<'a> Parser<'a> {
                 self.recovery.pop();
 
                 let s = self.expression();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
-            ReturnKw => {
-                self.builder.start_node(ReturnKw, self.s_loc);
+            kind @ (ReturnKw | AssertKw) => {
+                self.builder.start_node(kind, self.s_loc);
                 self.pass();
                 let s = self.expression();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
+            kind @ (BreakKw | ContinueKw) => {
+                self.builder.start_node(kind, self.s_loc);
+                self.bump();
+                if let ParseAction::Return(s) = self.label(0, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
             FileKw => {
                 self.builder.start_node(FileKw, self.s_loc);
                 self.pass();
@@ -122,7 +199,7 @@ impl<'a> Parser<'a> {
                     return s;
                 }
                 self.recovery.pop();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 ParseRecovery::Ok
             }
             OpenBrace => {
@@ -148,52 +225,75 @@ impl<'a> Parser<'a> {
                             self.pass();
                             break;
                         }
-                        _ => {
-                            if let ParseAction::Return(s) = self.expect_func(Self::statement, 1, 2)
-                            {
-                                return s;
+                        _ => match self.statement() {
+                            // the scope goes on after a statement that failed to parse
+                            ParseRecovery::Ok
+                            | ParseRecovery::SemiColon
+                            | ParseRecovery::Recovered(0) => (),
+                            s => {
+                                self.recovery.pop();
+                                self.builder.finish_node(self.e_loc, |_| None);
+                                self.builder.finish_node(self.e_loc, |_| None);
+                                return match s {
+                                    ParseRecovery::Recovered(r) => ParseRecovery::Recovered(r - 1),
+                                    s => s,
+                                };
                             }
-                        }
+                        },
                     }
                 }
                 self.recovery.pop();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 ParseRecovery::Ok
             }
             LoopKw => {
                 self.builder.start_node(Loop, self.s_loc);
                 self.recovery.extend([CloseParen, OpenParen]);
                 self.pass();
+                if let ParseAction::Return(s) = self.label(2, 2) {
+                    return s;
+                }
                 if let ParseAction::Return(s) = self.expect(&[OpenParen], 2, 2, Some(false)) {
                     return s;
                 }
                 self.recovery.pop();
 
+                let mut header = true;
                 for syntax in [Self::statement, Self::expression] {
                     if self.current_syntax() != SemiColon {
                         let s = syntax(self);
-                        if matches!(s, ParseRecovery::Recovered(2..) | ParseRecovery::Eof) {
+                        if let ParseRecovery::Recovered(1..) | ParseRecovery::Eof = s {
                             self.recovery.pop();
                             self.builder.finish_node(self.e_loc, |_| None);
                             self.builder.finish_node(self.e_loc, |_| None);
-                            self.bump();
-                            return s;
+                            return match s {
+                                ParseRecovery::Recovered(r) => ParseRecovery::Recovered(r - 1),
+                                s => s,
+                            };
                         }
                     }
 
-                    if let ParseAction::Return(s) = self.expect(&[SemiColon], 1, 2, Some(true)) {
-                        return s;
+                    match self.expect(&[SemiColon], 1, 2, None) {
+                        ParseAction::Found => self.bump(),
+                        // the header was cut short by its closing parenthesis
+                        ParseAction::Recovered(_) => {
+                            header = false;
+                            break;
+                        }
+                        ParseAction::Return(s) => return s,
                     }
                 }
 
-                if self.current_syntax() != CloseParen {
+                if header && self.current_syntax() != CloseParen {
                     let s = self.statement();
-                    if matches!(s, ParseRecovery::Recovered(2..) | ParseRecovery::Eof) {
+                    if let ParseRecovery::Recovered(1..) | ParseRecovery::Eof = s {
                         self.recovery.pop();
                         self.builder.finish_node(self.e_loc, |_| None);
                         self.builder.finish_node(self.e_loc, |_| None);
-                        self.bump();
-                        return s;
+                        return match s {
+                            ParseRecovery::Recovered(r) => ParseRecovery::Recovered(r - 1),
+                            s => s,
+                        };
                     }
                 }
 
@@ -204,14 +304,54 @@ impl<'a> Parser<'a> {
                 self.recovery.pop();
 
                 let s = self.statement();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            WhileKw => {
+                self.builder.start_node(While, self.s_loc);
+                self.pass();
+                if let ParseAction::Return(s) = self.label(0, 2) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect_func(Self::condition, 0, 2) {
+                    return s;
+                }
+                let s = self.statement();
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            ForKw => {
+                self.builder.start_node(For, self.s_loc);
+                self.pass();
+                if let ParseAction::Return(s) = self.label(0, 2) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 0, 2, Some(true)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[InKw], 0, 2, Some(false)) {
+                    return s;
+                }
+                self.recovery.push(DotDot);
+                if let ParseAction::Return(s) = self.expect_func(Self::expression, 1, 2) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[DotDot], 1, 2, Some(false)) {
+                    return s;
+                }
+                self.recovery.pop();
+                if let ParseAction::Return(s) = self.expect_func(Self::condition, 0, 2) {
+                    return s;
+                }
+                let s = self.statement();
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
             IfKw => {
                 self.builder.start_node(If, self.s_loc);
                 self.pass();
                 self.recovery.push(ElseKw);
-                match self.expect_func(Self::expression, 1, 2) {
+                match self.expect_func(Self::condition, 1, 2) {
                     ParseAction::Return(s) => return s,
                     ParseAction::Found => {
                         if let ParseAction::Return(s) = self.expect_func(Self::statement, 1, 2) {
@@ -227,48 +367,134 @@ impl<'a> Parser<'a> {
                     self.pass();
                     s = self.statement();
                 }
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            StructKw => {
+                self.builder.start_node(Struct, self.s_loc);
+                self.recovery.extend([CloseBrace, Comma]);
+                self.pass();
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 2, 2, Some(true)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[OpenBrace], 2, 2, Some(false)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.separated(Self::field_kind, CloseBrace, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            EnumKw => {
+                self.builder.start_node(Enum, self.s_loc);
+                self.recovery.extend([CloseBrace, Comma]);
+                self.pass();
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 2, 2, Some(true)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[OpenBrace], 2, 2, Some(false)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.separated(Self::variant, CloseBrace, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            TypeKw => {
+                self.builder.start_node(TypeAlias, self.s_loc);
+                self.recovery.push(Assign);
+                self.pass();
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 1, 2, Some(true)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[Assign], 1, 2, Some(false)) {
+                    return s;
+                }
+                self.recovery.pop();
+
+                let s = self.kind();
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
             FxKw => {
                 self.builder.start_node(Fx, self.s_loc);
+                // the next expected syntax is on top of the recovery
                 self.recovery
-                    .extend([Identifier, OpenParen, Comma, Identifier, CloseParen]);
+                    .extend([CloseParen, Comma, Identifier, OpenParen, Identifier]);
                 self.pass();
-                if let ParseAction::Return(s) = self.expect(&[Identifier], 6, 2, Some(true)) {
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 5, 2, Some(true)) {
                     return s;
                 }
                 self.recovery.pop();
 
-                if let ParseAction::Return(s) = self.expect(&[OpenParen], 5, 2, Some(false)) {
+                if let ParseAction::Return(s) = self.expect(&[OpenParen], 4, 2, Some(false)) {
                     return s;
                 }
                 self.recovery.pop();
 
-                while self.current_syntax() != CloseParen {
-                    match self.expect(&[Identifier], 4, 2, None) {
+                // a brace starts the body of parameters left unclosed
+                while !matches!(self.current_syntax(), CloseParen | OpenBrace) {
+                    // only the last parameter can collect the extra arguments
+                    let variadic = self.current_syntax() == Ellipsis;
+                    if variadic {
+                        self.bump();
+                    }
+                    match self.expect(&[Identifier], 3, 2, None) {
                         ParseAction::Found | ParseAction::Recovered(0) => self.bump(),
                         ParseAction::Recovered(1) => (),
                         ParseAction::Recovered(_) => break,
                         ParseAction::Return(s) => return s,
                     }
-                    if self.current_syntax() != Comma {
+                    // the colon is kept so that the kind isn't taken for the one of the result
+                    if self.current_syntax() == Colon {
+                        self.bump();
+                        if let ParseAction::Return(s) = self.expect_func(Self::kind, 3, 2) {
+                            return s;
+                        }
+                    }
+                    if !variadic && self.current_syntax() == Assign {
+                        self.bump();
+                        if let ParseAction::Return(s) = self.expect_func(Self::expression, 3, 2) {
+                            return s;
+                        }
+                    }
+                    if variadic || self.current_syntax() != Comma {
                         break;
                     }
                     self.pass();
                 }
                 self.recovery.pop();
                 self.recovery.pop();
+                self.recovery.pop();
 
-                if let ParseAction::Return(s) = self.expect(&[CloseParen], 1, 2, Some(false)) {
+                if self.current_syntax() == CloseParen {
+                    self.pass();
+                } else if let Some(s) = self.unclosed_params() {
+                    self.builder.finish_node(self.e_loc, |_| None);
+                    self.builder.finish_node(self.e_loc, |_| None);
                     return s;
                 }
-                self.recovery.pop();
+
+                if self.current_syntax() == Arrow {
+                    self.pass();
+                    if let ParseAction::Return(s) = self.expect_func(Self::kind, 0, 2) {
+                        return s;
+                    }
+                }
 
                 let s = self.statement();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
+            // a semicolon ends the statement before it, so nothing after it continues it
+            SemiColon => {
+                self.builder.start_node(Value, self.s_loc);
+                self.bump();
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
             _ => {
                 let checkpoint = self.builder.checkpoint(self.s_loc);
                 self.recovery.extend(SyntaxKind::ASSIGNMENT);
@@ -280,13 +506,21 @@ impl<'a> Parser<'a> {
                     self.bump();
 
                     self.expression();
-                    self.builder.finish_node(self.e_loc, |_| None);
+                    self.builder.finish_node(self.p_loc, |_| None);
                 }
                 self.recovery.drain(self.recovery.len() - 11..);
                 ParseRecovery::Ok
             }
         };
-        self.builder.finish_node(self.e_loc, |_| None);
+        self.builder.finish_node(self.p_loc, |_| None);
+        s
+    }
+
+    /// Parses the expression before the body of a statement, where `S {}` starts the body
+    fn condition(&mut self) -> ParseRecovery {
+        let empty_struct = std::mem::replace(&mut self.empty_struct, false);
+        let s = self.expression();
+        self.empty_struct = empty_struct;
         s
     }
 
@@ -310,7 +544,7 @@ impl<'a> Parser<'a> {
             }
             self.recovery.pop();
             self.expression();
-            self.builder.finish_node(self.e_loc, |_| None);
+            self.builder.finish_node(self.p_loc, |_| None);
         }
 
         ParseRecovery::Ok
@@ -323,7 +557,7 @@ impl<'a> Parser<'a> {
                 self.builder.start_node(UnaryOp, self.s_loc);
                 self.bump();
                 let s = self.expression();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
             _ => self.binary_op(
@@ -362,14 +596,34 @@ impl<'a> Parser<'a> {
                 self.builder.start_node(UnaryOp, self.s_loc);
                 self.bump();
                 let s = self.factor();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
-            _ => self.cast(),
+            _ => self.power(),
         }
     }
 
-    /// Parses type casting
+    /// Parses exponentiation: **, which groups from the right and under the negative sign, so
+    /// `-2 ** 2` is `-(2 ** 2)`
+    fn power(&mut self) -> ParseRecovery {
+        let start = self.builder.checkpoint(self.s_loc);
+        self.recovery.push(Pow);
+        if let ParseAction::Return(s) = self.expect_func(Self::cast, 1, 0) {
+            return s;
+        }
+        if self.current_syntax() == Pow {
+            self.builder.start_node_at(start, BinaryOp);
+            self.bump();
+            if let ParseAction::Return(s) = self.expect_func(Self::factor, 1, 1) {
+                return s;
+            }
+            self.builder.finish_node(self.p_loc, |_| None);
+        }
+        self.recovery.pop();
+        ParseRecovery::Ok
+    }
+
+    /// Parses type casting: `<kind> value` and `value as kind`
     fn cast(&mut self) -> ParseRecovery {
         match self.current_syntax() {
             LessThan => {
@@ -384,51 +638,170 @@ impl<'a> Parser<'a> {
                 }
                 self.recovery.pop();
                 let s = self.call();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
-            _ => self.call(),
+            _ => {
+                let start = self.builder.checkpoint(self.s_loc);
+                let s = self.call();
+                if !matches!(s, ParseRecovery::Ok) {
+                    return s;
+                }
+                while self.current_syntax() == AsKw {
+                    self.builder.start_node_at(start, Cast);
+                    self.pass();
+                    if let ParseAction::Return(s) = self.expect_func(Self::kind, 0, 1) {
+                        return s;
+                    }
+                    self.builder.finish_node(self.p_loc, |_| None);
+                }
+                ParseRecovery::Ok
+            }
         }
     }
 
-    /// Parses function calls
+    /// Parses function calls, method calls, indexing and field access
     fn call(&mut self) -> ParseRecovery {
         let start = self.builder.checkpoint(self.s_loc);
-        self.recovery.push(OpenParen);
-        if let ParseAction::Return(s) = self.expect_func(Self::value, 1, 0) {
+        self.recovery.extend([OpenParen, OpenBracket]);
+        if let ParseAction::Return(s) = self.expect_func(Self::value, 2, 0) {
             return s;
         }
-        self.recovery.pop();
-        if self.current_syntax() == OpenParen {
-            self.builder.start_node_at(start, Call);
-            self.pass();
-            self.recovery.extend([CloseParen, Comma]);
-            while self.current_syntax() != CloseParen {
-                match self.expect_func(Self::expression, 2, 1) {
-                    ParseAction::Found | ParseAction::Recovered(1) => (),
-                    ParseAction::Recovered(_) => break,
-                    ParseAction::Return(s) => return s,
+        self.recovery.truncate(self.recovery.len() - 2);
+        loop {
+            match self.current_syntax() {
+                OpenParen => {
+                    self.builder.start_node_at(start, Call);
+                    self.pass();
+                    self.recovery.extend([CloseParen, Comma]);
+                    while self.current_syntax() != CloseParen {
+                        match self.expect_func(Self::argument, 2, 1) {
+                            ParseAction::Found | ParseAction::Recovered(1) => (),
+                            ParseAction::Recovered(_) => break,
+                            ParseAction::Return(s) => return s,
+                        }
+                        if self.current_syntax() != Comma {
+                            break;
+                        }
+                        self.pass();
+                    }
+                    self.recovery.pop();
+                    if let ParseAction::Return(s) = self.expect(&[CloseParen], 1, 1, Some(false)) {
+                        return s;
+                    }
+                    self.recovery.pop();
+                    self.builder.finish_node(self.p_loc, |_| None);
+                }
+                OpenBracket => {
+                    self.builder.start_node_at(start, Index);
+                    self.pass();
+                    self.recovery.push(CloseBracket);
+                    if let ParseAction::Return(s) = self.expect_func(Self::expression, 1, 1) {
+                        return s;
+                    }
+                    if let ParseAction::Return(s) = self.expect(&[CloseBracket], 1, 1, Some(false))
+                    {
+                        return s;
+                    }
+                    self.recovery.pop();
+                    self.builder.finish_node(self.p_loc, |_| None);
                 }
-                if self.current_syntax() != Comma {
-                    break;
+                Dot if self.nth_syntax(1) == Identifier && self.nth_syntax(2) == OpenParen => {
+                    self.builder.start_node_at(start, MethodCall);
+                    self.pass();
+                    self.bump();
+                    self.pass();
+                    self.recovery.extend([CloseParen, Comma]);
+                    if let ParseAction::Return(s) = self.separated(Self::argument, CloseParen, 1) {
+                        return s;
+                    }
+                    self.builder.finish_node(self.p_loc, |_| None);
                 }
-                self.pass();
-            }
-            self.recovery.pop();
-            if let ParseAction::Return(s) = self.expect(&[CloseParen], 1, 1, Some(false)) {
-                return s;
+                // `a.0.1` is lexed with the float `0.1`, which is split into two accesses
+                Dot if self.nth_syntax(1) == Float
+                    && self.source[self.nth_span(1)].contains('.') =>
+                {
+                    self.pass();
+                    let span = self.current_syntax_span();
+                    let dot = span.start + self.source[span.clone()].find('.').unwrap();
+                    self.pass();
+                    self.builder.start_node_at(start, Access);
+                    self.builder.push(Number, span.start..dot, |_| None);
+                    self.builder.finish_node(dot, |_| None);
+                    self.builder.start_node_at(start, Access);
+                    self.builder.push(Number, dot + 1..span.end, |_| None);
+                    self.builder.finish_node(span.end, |_| None);
+                }
+                Dot => {
+                    self.builder.start_node_at(start, Access);
+                    self.pass();
+                    if let ParseAction::Return(s) =
+                        self.expect(&[Identifier, Number], 0, 1, Some(true))
+                    {
+                        return s;
+                    }
+                    self.builder.finish_node(self.p_loc, |_| None);
+                }
+                _ => break,
             }
-            self.recovery.pop();
-            self.builder.finish_node(self.e_loc, |_| None);
         }
         ParseRecovery::Ok
     }
 
-    /// Parses atoms: Number, Char, String, ;, In, Ident, (), pointer, deref, list
+    /// Parses atoms: Number, Float, Char, String, ;, In, Ident, (), pointer, deref, list
     fn value(&mut self) -> ParseRecovery {
         self.builder.start_node(Value, self.s_loc);
         let s = match self.current_syntax() {
-            Number | Char | String | SemiColon | InKw | Identifier => {
+            Identifier
+                if self.nth_syntax(1) == OpenBrace
+                    && (self.nth_syntax(2) == Identifier && self.nth_syntax(3) == Colon
+                        || self.empty_struct && self.nth_syntax(2) == CloseBrace) =>
+            {
+                self.builder.start_node(StructLit, self.s_loc);
+                self.recovery.extend([CloseBrace, Comma]);
+                self.bump();
+                self.pass();
+                if let ParseAction::Return(s) = self.separated(Self::field_value, CloseBrace, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            MatchKw => {
+                self.builder.start_node(Match, self.s_loc);
+                self.recovery.extend([CloseBrace, Comma]);
+                self.pass();
+                if let ParseAction::Return(s) = self.expect_func(Self::condition, 2, 2) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[OpenBrace], 2, 2, Some(false)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.separated(Self::arm, CloseBrace, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            Identifier if self.nth_syntax(1) == ColonColon => {
+                self.builder.start_node(EnumLit, self.s_loc);
+                self.bump();
+                self.pass();
+                if let ParseAction::Return(s) = self.expect(&[Identifier], 0, 2, Some(true)) {
+                    return s;
+                }
+                if self.current_syntax() == OpenParen {
+                    self.pass();
+                    self.recovery.extend([CloseParen, Comma]);
+                    if let ParseAction::Return(s) = self.separated(Self::expression, CloseParen, 2)
+                    {
+                        return s;
+                    }
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            Number | Float | Char | String | SemiColon | InKw | TrueKw | FalseKw | Identifier => {
                 self.bump();
                 ParseRecovery::Ok
             }
@@ -440,35 +813,72 @@ impl<'a> Parser<'a> {
                 ParseRecovery::Ok
             }
             OpenParen => {
+                let start = self.builder.checkpoint(self.s_loc);
                 self.pass();
-                self.recovery.push(CloseParen);
-                if let ParseAction::Return(s) = self.expect_func(Self::expression, 1, 0) {
+                self.recovery.extend([CloseParen, Comma]);
+                if let ParseAction::Return(s) = self.expect_func(Self::expression, 2, 1) {
                     return s;
                 }
-                if let ParseAction::Return(s) = self.expect(&[CloseParen], 1, 0, Some(false)) {
-                    return s;
+                if self.current_syntax() == Comma {
+                    self.builder.start_node_at(start, Tuple);
+                    self.pass();
+                    if let ParseAction::Return(s) = self.separated(Self::expression, CloseParen, 2)
+                    {
+                        return s;
+                    }
+                    self.builder.finish_node(self.p_loc, |_| None);
+                } else {
+                    self.recovery.pop();
+                    if let ParseAction::Return(s) = self.expect(&[CloseParen], 1, 1, Some(false)) {
+                        return s;
+                    }
+                    self.recovery.pop();
                 }
-                self.recovery.pop();
                 ParseRecovery::Ok
             }
+            Or => {
+                self.builder.start_node(Lambda, self.s_loc);
+                self.recovery.extend([Or, Comma]);
+                self.pass();
+                if let ParseAction::Return(s) = self.separated(Self::param, Or, 2) {
+                    return s;
+                }
+                let s = self.expression();
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
             OpenBrace => {
                 self.builder.start_node(Pointer, self.s_loc);
                 self.recovery.push(CloseBrace);
                 self.pass();
-                if let ParseAction::Return(s) = self.expect_func(Self::expression, 1, 1) {
+                if let ParseAction::Return(s) = self.expect_func(Self::expression, 1, 2) {
                     return s;
                 }
-                if let ParseAction::Return(s) = self.expect(&[CloseBrace], 1, 1, Some(false)) {
+                if let ParseAction::Return(s) = self.expect(&[CloseBrace], 1, 2, Some(false)) {
                     return s;
                 }
                 self.recovery.pop();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 ParseRecovery::Ok
             }
+            // `**p` is lexed as a power, it is split into two dereferences
+            Pow => {
+                let span = self.current_syntax_span();
+                self.builder.start_node(UnaryOp, span.start);
+                self.builder.push(Mul, span.start..span.start + 1, |_| None);
+                self.builder.start_node(UnaryOp, span.start + 1);
+                self.builder.push(Mul, span.start + 1..span.end, |_| None);
+                self.pass();
+                let s = self.call();
+                self.builder.finish_node(self.p_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            // the dereference applies to the calls, indexes and fields after it
             Mul => {
                 self.builder.start_node(UnaryOp, self.s_loc);
                 self.bump();
-                let s = self.value();
+                let s = self.call();
                 self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
@@ -476,22 +886,9 @@ impl<'a> Parser<'a> {
                 self.builder.start_node(List, self.s_loc);
                 self.pass();
                 self.recovery.extend([CloseBracket, Comma]);
-                while self.current_syntax() != CloseBracket {
-                    match self.expect_func(Self::expression, 2, 1) {
-                        ParseAction::Found | ParseAction::Recovered(1) => (),
-                        ParseAction::Recovered(_) => break,
-                        ParseAction::Return(s) => return s,
-                    }
-                    if self.current_syntax() != Comma {
-                        break;
-                    }
-                    self.pass();
-                }
-                self.recovery.pop();
-                if let ParseAction::Return(s) = self.expect(&[CloseBracket], 1, 1, Some(false)) {
+                if let ParseAction::Return(s) = self.separated(Self::expression, CloseBracket, 2) {
                     return s;
                 }
-                self.recovery.pop();
                 self.builder.finish_node(self.p_loc, |_| None);
                 ParseRecovery::Ok
             }
@@ -504,23 +901,245 @@ impl<'a> Parser<'a> {
     /// Parses kinds (types)
     fn kind(&mut self) -> ParseRecovery {
         match self.current_syntax() {
-            ByteKw | Identifier | SemiColon => {
+            ByteKw | BoolKw | FloatKw | CharKw | Identifier | SemiColon => {
                 self.builder.start_node(Kind, self.s_loc);
                 self.bump();
-                self.builder.finish_node(self.e_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
                 ParseRecovery::Ok
             }
             Mul => {
-                self.builder.start_node(UnaryOp, self.s_loc);
+                self.builder.start_node(Kind, self.s_loc);
                 self.bump();
                 let s = self.kind();
                 self.builder.finish_node(self.p_loc, |_| None);
                 s
             }
+            // `**kind` is lexed as a power, it is split into two pointers
+            Pow => {
+                let span = self.current_syntax_span();
+                self.builder.start_node(Kind, span.start);
+                self.builder.push(Mul, span.start..span.start + 1, |_| None);
+                self.builder.start_node(Kind, span.start + 1);
+                self.builder.push(Mul, span.start + 1..span.end, |_| None);
+                self.pass();
+                let s = self.kind();
+                self.builder.finish_node(self.p_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
+                s
+            }
+            OpenParen => {
+                self.builder.start_node(Kind, self.s_loc);
+                self.builder.start_node(Tuple, self.s_loc);
+                self.recovery.extend([CloseParen, Comma]);
+                self.pass();
+                if let ParseAction::Return(s) = self.separated(Self::kind, CloseParen, 2) {
+                    return s;
+                }
+                self.builder.finish_node(self.p_loc, |_| None);
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
+            OpenBracket => {
+                self.builder.start_node(Kind, self.s_loc);
+                self.recovery.push(CloseBracket);
+                self.pass();
+                if let ParseAction::Return(s) = self.expect_func(Self::kind, 1, 1) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[SemiColon], 1, 1, Some(false)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[Number], 1, 1, Some(true)) {
+                    return s;
+                }
+                if let ParseAction::Return(s) = self.expect(&[CloseBracket], 1, 1, Some(false)) {
+                    return s;
+                }
+                self.recovery.pop();
+                self.builder.finish_node(self.p_loc, |_| None);
+                ParseRecovery::Ok
+            }
             _ => self.unexpected_syntax(vec![Kind]),
         }
     }
 
+    /// Parses comma separated items up to the closing syntax.
+    /// Expects `close` and `,` to be the last two recovery tokens and pops them
+    fn separated(
+        &mut self,
+        item: fn(&mut Self) -> ParseRecovery,
+        close: SyntaxKind,
+        node: usize,
+    ) -> ParseAction {
+        while self.current_syntax() != close {
+            match self.expect_func(item, 2, node) {
+                ParseAction::Found | ParseAction::Recovered(0) => (),
+                ParseAction::Recovered(_) => break,
+                ParseAction::Return(s) => return ParseAction::Return(s),
+            }
+            if self.current_syntax() != Comma {
+                break;
+            }
+            self.pass();
+        }
+        self.recovery.pop();
+        if let ParseAction::Return(s) = self.expect(&[close], 1, node, Some(false)) {
+            return ParseAction::Return(s);
+        }
+        self.recovery.pop();
+        ParseAction::Found
+    }
+
+    /// Parses a variant of an enum definition: `name` or `name(kind, ...)`
+    fn variant(&mut self) -> ParseRecovery {
+        self.builder.start_node(Variant, self.s_loc);
+        if let ParseAction::Return(s) = self.expect(&[Identifier], 0, 1, Some(true)) {
+            return s;
+        }
+        if self.current_syntax() == OpenParen {
+            self.pass();
+            self.recovery.extend([CloseParen, Comma]);
+            if let ParseAction::Return(s) = self.separated(Self::kind, CloseParen, 1) {
+                return s;
+            }
+        }
+        self.builder.finish_node(self.p_loc, |_| None);
+        ParseRecovery::Ok
+    }
+
+    /// Parses a field of a struct definition: `name: kind`
+    fn field_kind(&mut self) -> ParseRecovery {
+        self.field(Self::kind)
+    }
+
+    /// Parses a field of a struct literal: `name: value`
+    fn field_value(&mut self) -> ParseRecovery {
+        self.field(Self::expression)
+    }
+
+    fn field(&mut self, value: fn(&mut Self) -> ParseRecovery) -> ParseRecovery {
+        self.builder.start_node(Field, self.s_loc);
+        self.recovery.push(Colon);
+        if let ParseAction::Return(s) = self.expect(&[Identifier], 1, 1, Some(true)) {
+            return s;
+        }
+        if let ParseAction::Return(s) = self.expect(&[Colon], 1, 1, Some(false)) {
+            return s;
+        }
+        self.recovery.pop();
+        let s = value(self);
+        self.builder.finish_node(self.p_loc, |_| None);
+        s
+    }
+
+    /// Parses an argument of a call: `value` or `name: value`
+    fn argument(&mut self) -> ParseRecovery {
+        if self.current_syntax() == Identifier && self.nth_syntax(1) == Colon {
+            self.field(Self::expression)
+        } else {
+            self.expression()
+        }
+    }
+
+    /// Parses a parameter of a lambda: `name` or `name: kind`
+    fn param(&mut self) -> ParseRecovery {
+        self.builder.start_node(Param, self.s_loc);
+        if let ParseAction::Return(s) = self.expect(&[Identifier], 0, 1, Some(true)) {
+            return s;
+        }
+        if self.current_syntax() == Colon {
+            self.pass();
+            if let ParseAction::Return(s) = self.expect_func(Self::kind, 0, 1) {
+                return s;
+            }
+        }
+        self.builder.finish_node(self.p_loc, |_| None);
+        ParseRecovery::Ok
+    }
+
+    /// Parses an arm of a match: `pattern => value`
+    fn arm(&mut self) -> ParseRecovery {
+        self.builder.start_node(Arm, self.s_loc);
+        self.recovery.push(FatArrow);
+        if let ParseAction::Return(s) = self.expect_func(Self::pattern, 1, 1) {
+            return s;
+        }
+        if let ParseAction::Return(s) = self.expect(&[FatArrow], 1, 1, Some(false)) {
+            return s;
+        }
+        self.recovery.pop();
+        let s = self.expression();
+        self.builder.finish_node(self.p_loc, |_| None);
+        s
+    }
+
+    /// Parses a pattern: a number, a binding or `enum::variant(binding, ...)`
+    fn pattern(&mut self) -> ParseRecovery {
+        self.builder.start_node(Pattern, self.s_loc);
+        if let ParseAction::Return(s) = self.expect(&[Number, Identifier], 0, 1, None) {
+            return s;
+        }
+        let variant = self.current_syntax() == Identifier && self.nth_syntax(1) == ColonColon;
+        self.bump();
+        if variant {
+            self.pass();
+            if let ParseAction::Return(s) = self.expect(&[Identifier], 0, 1, Some(true)) {
+                return s;
+            }
+            if self.current_syntax() == OpenParen {
+                self.pass();
+                self.recovery.extend([CloseParen, Comma]);
+                if let ParseAction::Return(s) = self.separated(Self::binding, CloseParen, 1) {
+                    return s;
+                }
+            }
+        }
+        self.builder.finish_node(self.p_loc, |_| None);
+        ParseRecovery::Ok
+    }
+
+    /// Parses the destructuring target of a let: `(a, b)` or `name { field, ... }`
+    fn destructure(&mut self) -> ParseRecovery {
+        self.builder.start_node(Pattern, self.s_loc);
+        let close = match self.current_syntax() {
+            OpenParen => CloseParen,
+            _ => {
+                self.bump();
+                CloseBrace
+            }
+        };
+        // the opening syntax tells tuples and structs apart
+        self.bump();
+        self.recovery.extend([close, Comma]);
+        if let ParseAction::Return(s) = self.separated(Self::binding, close, 1) {
+            return s;
+        }
+        self.builder.finish_node(self.p_loc, |_| None);
+        ParseRecovery::Ok
+    }
+
+    /// Parses a name bound by a pattern
+    fn binding(&mut self) -> ParseRecovery {
+        match self.expect(&[Identifier], 0, 0, Some(true)) {
+            ParseAction::Return(s) => s,
+            _ => ParseRecovery::Ok,
+        }
+    }
+
+    /// Parses an optional label of a loop, break or continue: `:name`
+    fn label(&mut self, current_recovery: usize, node: usize) -> ParseAction {
+        if self.current_syntax() != Colon {
+            return ParseAction::Found;
+        }
+        self.builder.start_node(Label, self.s_loc);
+        self.pass();
+        let s = self.expect(&[Identifier], current_recovery, node + 1, Some(true));
+        if !matches!(s, ParseAction::Return(_)) {
+            self.builder.finish_node(self.p_loc, |_| None);
+        }
+        s
+    }
+
     /// Peforms binary operations over a set of operators for the fiven function
     fn binary_op(
         &mut self,
@@ -538,7 +1157,7 @@ impl<'a> Parser<'a> {
             if let ParseAction::Return(s) = self.expect_func(func, ops.len(), 1) {
                 return s;
             }
-            self.builder.finish_node(self.e_loc, |_| None);
+            self.builder.finish_node(self.p_loc, |_| None);
         }
         self.recovery.drain(self.recovery.len() - ops.len()..);
         ParseRecovery::Ok
@@ -563,7 +1182,8 @@ impl<'a> Parser<'a> {
                     }
                     self.recovery
                         .drain(self.recovery.len() - current_recovery..);
-                    ParseAction::Return(s)
+                    // the index is relative to the recovery of the caller
+                    ParseAction::Return(ParseRecovery::Recovered(r - current_recovery))
                 }
                 ParseRecovery::Eof | ParseRecovery::SemiColon => {
                     for _ in 0..node {
@@ -574,7 +1194,8 @@ impl<'a> Parser<'a> {
                     ParseAction::Return(s)
                 }
                 ParseRecovery::Recovered(s) => {
-                    if let Some(bump) = bump {
+                    // only the expected syntax is consumed after recovering
+                    if let Some(bump) = bump.filter(|_| expect.contains(&self.current_syntax())) {
                         if bump {
                             self.bump()
                         } else {
@@ -613,7 +1234,7 @@ impl<'a> Parser<'a> {
                 }
                 self.recovery
                     .drain(self.recovery.len() - current_recovery..);
-                ParseAction::Return(s)
+                ParseAction::Return(ParseRecovery::Recovered(r - current_recovery))
             }
             ParseRecovery::Eof | ParseRecovery::SemiColon => {
                 for _ in 0..node {
@@ -632,7 +1253,11 @@ impl<'a> Parser<'a> {
     fn unexpected_syntax(&mut self, expected: Vec<SyntaxKind>) -> ParseRecovery {
         self.builder.start_node(Error, self.s_loc);
         let span = self.current_syntax_span();
-        self.bump();
+        // a recovery syntax is left to the parser expecting it, and a semicolon to the statements
+        let current = self.current_syntax();
+        if current != SemiColon && !self.recovery.contains(&current) {
+            self.bump();
+        }
         self.errors.push(Error::error(
             ErrorKind::UnexpectedSyntax { expected },
             span,
@@ -643,6 +1268,46 @@ impl<'a> Parser<'a> {
         s
     }
 
+    /// Reports parameters missing their closing parenthesis and skips the rest of their line, so
+    /// that the body is still parsed when it follows on the same line or starts with a brace
+    fn unclosed_params(&mut self) -> Option<ParseRecovery> {
+        self.builder.start_node(Error, self.s_loc);
+        let span = self.current_syntax_span();
+        self.errors.push(Error::error(
+            ErrorKind::UnexpectedSyntax {
+                expected: vec![CloseParen],
+            },
+            span,
+            self.source,
+        ));
+        loop {
+            match self.current_syntax() {
+                Eof | SemiColon | OpenBrace | CloseBrace => break,
+                _ if self.line_break() => break,
+                _ => self.bump(),
+            }
+        }
+        self.builder.finish_node(self.p_loc, |_| None);
+        match self.current_syntax() {
+            Eof => Some(ParseRecovery::Eof),
+            SemiColon => Some(ParseRecovery::SemiColon),
+            _ => None,
+        }
+    }
+
+    /// Whether the current syntax starts a new line
+    fn line_break(&self) -> bool {
+        let end = match self.cursor.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some((_, span)) => span.end,
+            None => return false,
+        };
+        let start = self
+            .tokens
+            .get(self.cursor)
+            .map_or(end, |(_, span)| span.start);
+        self.source[end..start].contains('\n')
+    }
+
     /// Bumps till a recoverable syntax is found
     fn recover(&mut self) -> ParseRecovery {
         loop {
@@ -667,8 +1332,9 @@ impl<'a> Parser<'a> {
 
     /// Consumes current syntax and push to builder.
     fn bump(&mut self) {
-        if let Some((kind, span)) = self.tokens.next() {
+        if let Some((kind, span)) = self.tokens.get(self.cursor).cloned() {
             self.builder.push(kind.unwrap_or(Error), span, |_| None);
+            self.cursor += 1;
         }
         self.p_loc = self.e_loc;
         self.s_loc = self.s_loc();
@@ -677,7 +1343,7 @@ impl<'a> Parser<'a> {
 
     /// Consumes the current syntax
     fn pass(&mut self) {
-        self.tokens.next();
+        self.cursor = (self.cursor + 1).min(self.tokens.len());
         self.p_loc = self.e_loc;
         self.s_loc = self.s_loc();
         self.e_loc = self.e_loc();
@@ -686,29 +1352,45 @@ impl<'a> Parser<'a> {
     /// Peeks the current syntax
     fn current_syntax(&mut self) -> SyntaxKind {
         self.tokens
-            .peek()
+            .get(self.cursor)
             .map(|&(kind, _)| kind.unwrap_or(Error))
             .unwrap_or(Eof)
     }
 
+    /// Peeks the n-th syntax after the current one
+    fn nth_syntax(&self, n: usize) -> SyntaxKind {
+        self.tokens
+            .get(self.cursor + n)
+            .map(|&(kind, _)| kind.unwrap_or(Error))
+            .unwrap_or(Eof)
+    }
+
+    /// Span of the nth syntax from the current one
+    fn nth_span(&self, n: usize) -> Span {
+        self.tokens
+            .get(self.cursor + n)
+            .map(|(_, span)| span.clone())
+            .unwrap_or(self.source.len()..self.source.len())
+    }
+
     /// Current span of the syntax
     fn current_syntax_span(&mut self) -> Span {
         self.tokens
-            .peek()
+            .get(self.cursor)
             .map(|(_, span)| span.clone())
             .unwrap_or(self.source.len()..self.source.len() + 1)
     }
 
     fn s_loc(&mut self) -> usize {
         self.tokens
-            .peek()
+            .get(self.cursor)
             .map(|(_, span)| span.start)
             .unwrap_or(self.source.len())
     }
 
     fn e_loc(&mut self) -> usize {
         self.tokens
-            .peek()
+            .get(self.cursor)
             .map(|(_, span)| span.end)
             .unwrap_or(self.source.len())
     }
@@ -0,0 +1,454 @@
+use crate::tree::{Leaf, LeafId, Node, NodeId, TreeElement};
+
+use super::syntax::{ParseTree, SyntaxKind, SyntaxKind::*};
+
+/// Renders a parse tree back into source code, with one statement per line and indented scopes.
+/// The tokens come out in the same order, so the result parses into the same tree. The `file`
+/// statements are left out since the files they include are already part of the tree
+pub fn to_source(tree: &ParseTree, source: &str) -> std::string::String {
+    let mut printer = Printer {
+        tree,
+        source,
+        out: std::string::String::new(),
+        indent: 0,
+    };
+    printer.statements(tree.node(ParseTree::ROOT));
+    printer.out.push('\n');
+    printer.out
+}
+
+struct Printer<'a> {
+    tree: &'a ParseTree,
+    source: &'a str,
+    out: std::string::String,
+    indent: usize,
+}
+
+impl<'a> Printer<'a> {
+    /// Prints the statements of the root or of a scope, a lone `;` stays on the line it ends
+    fn statements(&mut self, node: &Node<()>) {
+        let mut first = true;
+        for &child in node.children() {
+            let statement = self.tree.node(child);
+            if self.include(statement) {
+                continue;
+            }
+            if !first && !self.semicolon(statement) {
+                self.out.push('\n');
+                self.out.push_str(&"    ".repeat(self.indent));
+            }
+            self.node(statement);
+            first = false;
+        }
+    }
+
+    /// Whether the statement includes a file
+    fn include(&self, statement: &Node<()>) -> bool {
+        match statement.children()[..] {
+            [id] => self.tree.node(id).kind() == FileKw,
+            _ => false,
+        }
+    }
+
+    /// Whether the statement is a lone `;`
+    fn semicolon(&self, statement: &Node<()>) -> bool {
+        let value = match statement.children().first() {
+            Some(&id) => self.tree.node(id),
+            None => return false,
+        };
+        value.kind() == Value
+            && value.children().is_empty()
+            && value.leaves(self.tree).first().map(|leaf| leaf.kind()) == Some(SemiColon)
+    }
+
+    fn element(&mut self, element: TreeElement<NodeId, LeafId>) {
+        match element.get(self.tree) {
+            TreeElement::Node(node) => self.node(node),
+            TreeElement::Leaf(leaf) => self.leaf(leaf),
+        }
+    }
+
+    fn leaf(&mut self, leaf: &Leaf<SyntaxKind>) {
+        self.out.push_str(&self.source[leaf.span()]);
+    }
+
+    fn text(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    /// Prints the elements separated by commas
+    fn list(&mut self, elements: &[TreeElement<NodeId, LeafId>]) {
+        for (i, &element) in elements.iter().enumerate() {
+            if i > 0 {
+                self.text(", ");
+            }
+            self.element(element);
+        }
+    }
+
+    fn node(&mut self, node: &Node<()>) {
+        let children = node.children_with_leaves(self.tree).collect::<Vec<_>>();
+        match node.kind() {
+            Statement => self.list(&children),
+            Let | Const => {
+                self.text(if node.kind() == Let { "let " } else { "const " });
+                let (&value, rest) = children.split_last().unwrap();
+                for &element in rest {
+                    match element.get(self.tree).kind() {
+                        MutKw => self.text("mut "),
+                        Kind => {
+                            self.text(": ");
+                            self.element(element);
+                        }
+                        _ => self.element(element),
+                    }
+                }
+                self.text(" = ");
+                self.element(value);
+            }
+            ReLet | BinaryOp => {
+                self.element(children[0]);
+                self.text(" ");
+                self.element(children[1]);
+                self.text(" ");
+                self.element(children[2]);
+            }
+            ReturnKw | AssertKw => {
+                self.text(if node.kind() == ReturnKw {
+                    "return "
+                } else {
+                    "assert "
+                });
+                self.list(&children);
+            }
+            // the keyword is kept as a leaf
+            BreakKw | ContinueKw => {
+                self.element(children[0]);
+                if let Some(&label) = children.get(1) {
+                    self.text(" ");
+                    self.element(label);
+                }
+            }
+            Scope => {
+                if children.is_empty() {
+                    self.text("{}");
+                    return;
+                }
+                self.text("{");
+                self.indent += 1;
+                self.text(&format!("\n{}", "    ".repeat(self.indent)));
+                self.statements(node);
+                self.indent -= 1;
+                self.text(&format!("\n{}}}", "    ".repeat(self.indent)));
+            }
+            Loop => {
+                self.text("loop ");
+                let (&body, rest) = children.split_last().unwrap();
+                let header = self.label(rest);
+                self.text("(");
+                for (i, &element) in header.iter().enumerate() {
+                    self.element(element);
+                    if element.get(self.tree).kind() == SemiColon && i + 1 < header.len() {
+                        self.text(" ");
+                    }
+                }
+                self.text(") ");
+                self.element(body);
+            }
+            While => {
+                self.text("while ");
+                let rest = self.label(&children);
+                self.element(rest[0]);
+                self.text(" ");
+                self.element(rest[1]);
+            }
+            For => {
+                self.text("for ");
+                let rest = self.label(&children);
+                self.element(rest[0]);
+                self.text(" in ");
+                self.element(rest[1]);
+                self.text("..");
+                self.element(rest[2]);
+                self.text(" ");
+                self.element(rest[3]);
+            }
+            Label => {
+                self.text(":");
+                self.list(&children);
+            }
+            If => {
+                self.text("if ");
+                self.element(children[0]);
+                self.text(" ");
+                self.element(children[1]);
+                if let Some(&otherwise) = children.get(2) {
+                    self.text(" else ");
+                    self.element(otherwise);
+                }
+            }
+            Struct | Enum => {
+                self.text(if node.kind() == Struct {
+                    "struct "
+                } else {
+                    "enum "
+                });
+                self.element(children[0]);
+                self.text(" { ");
+                self.list(&children[1..]);
+                self.text(" }");
+            }
+            StructLit => {
+                self.element(children[0]);
+                self.text(" { ");
+                self.list(&children[1..]);
+                self.text(" }");
+            }
+            Field | Param => {
+                self.element(children[0]);
+                if let Some(&value) = children.get(1) {
+                    self.text(": ");
+                    self.element(value);
+                }
+            }
+            Variant => {
+                self.element(children[0]);
+                if children.len() > 1 {
+                    self.text("(");
+                    self.list(&children[1..]);
+                    self.text(")");
+                }
+            }
+            TypeAlias => {
+                self.text("type ");
+                self.element(children[0]);
+                self.text(" = ");
+                self.element(children[1]);
+            }
+            Fx => {
+                self.text("fx ");
+                self.element(children[0]);
+                self.text("(");
+                let (&body, mut params) = children[1..].split_last().unwrap();
+                // the kind of the last parameter follows a colon
+                let ret = match params {
+                    [.., colon, _] if colon.get(self.tree).kind() == Colon => None,
+                    [rest @ .., ret] if ret.get(self.tree).kind() == Kind => {
+                        params = rest;
+                        Some(*ret)
+                    }
+                    _ => None,
+                };
+                let mut first = true;
+                let mut params = params.iter().copied();
+                while let Some(element) = params.next() {
+                    match element.get(self.tree).kind() {
+                        Assign => {
+                            self.text(" = ");
+                            self.element(params.next().unwrap());
+                            continue;
+                        }
+                        Colon => {
+                            self.text(": ");
+                            self.element(params.next().unwrap());
+                            continue;
+                        }
+                        // the name of a variadic parameter follows its ellipsis
+                        Identifier if matches!(self.out.chars().last(), Some('.')) => (),
+                        _ if !first => self.text(", "),
+                        _ => (),
+                    }
+                    first = false;
+                    self.element(element);
+                }
+                self.text(")");
+                if let Some(ret) = ret {
+                    self.text(" -> ");
+                    self.element(ret);
+                }
+                self.text(" ");
+                self.element(body);
+            }
+            Lambda => {
+                let (&body, params) = children.split_last().unwrap();
+                self.text("|");
+                self.list(params);
+                self.text("| ");
+                self.element(body);
+            }
+            Value => match children[..] {
+                [TreeElement::Node(id)] if !self.atom(id) => {
+                    self.text("(");
+                    self.element(children[0]);
+                    self.text(")");
+                }
+                _ => self.list(&children),
+            },
+            Tuple if node.parent().map(|id| self.tree.node(id).kind()) == Some(Kind) => {
+                self.text("(");
+                self.list(&children);
+                self.text(")");
+            }
+            Tuple => {
+                self.text("(");
+                self.list(&children);
+                self.text(if children.len() == 1 { ",)" } else { ")" });
+            }
+            List => {
+                self.text("[");
+                self.list(&children);
+                self.text("]");
+            }
+            Pointer => {
+                self.text("{");
+                self.list(&children);
+                self.text("}");
+            }
+            UnaryOp => {
+                self.element(children[0]);
+                self.element(children[1]);
+            }
+            Ternary => {
+                self.element(children[0]);
+                self.text(" ? ");
+                self.element(children[1]);
+                self.text(" : ");
+                self.element(children[2]);
+            }
+            Cast if children[0].get(self.tree).kind() == Kind => {
+                self.text("<");
+                self.element(children[0]);
+                self.text("> ");
+                self.element(children[1]);
+            }
+            Cast => {
+                self.element(children[0]);
+                self.text(" as ");
+                self.element(children[1]);
+            }
+            Call => {
+                self.element(children[0]);
+                self.text("(");
+                self.list(&children[1..]);
+                self.text(")");
+            }
+            MethodCall => {
+                self.element(children[0]);
+                self.text(".");
+                self.element(children[1]);
+                self.text("(");
+                self.list(&children[2..]);
+                self.text(")");
+            }
+            Index => {
+                self.element(children[0]);
+                self.text("[");
+                self.element(children[1]);
+                self.text("]");
+            }
+            Access => {
+                self.element(children[0]);
+                self.text(".");
+                self.element(children[1]);
+            }
+            EnumLit => {
+                self.element(children[0]);
+                self.text("::");
+                self.element(children[1]);
+                if children.len() > 2 {
+                    self.text("(");
+                    self.list(&children[2..]);
+                    self.text(")");
+                }
+            }
+            Match => {
+                self.text("match ");
+                self.element(children[0]);
+                self.text(" { ");
+                self.list(&children[1..]);
+                self.text(" }");
+            }
+            Arm => {
+                self.element(children[0]);
+                self.text(" => ");
+                self.element(children[1]);
+            }
+            Pattern => self.pattern(&children),
+            Kind => match children[..] {
+                [size_kind, size] if size.get(self.tree).kind() == Number => {
+                    self.text("[");
+                    self.element(size_kind);
+                    self.text("; ");
+                    self.element(size);
+                    self.text("]");
+                }
+                _ => {
+                    for element in children {
+                        self.element(element);
+                    }
+                }
+            },
+            _ => self.list(&children),
+        }
+    }
+
+    /// Prints the label at the start of the elements if there is one, returning the others
+    fn label<'e>(
+        &mut self,
+        elements: &'e [TreeElement<NodeId, LeafId>],
+    ) -> &'e [TreeElement<NodeId, LeafId>] {
+        match elements.split_first() {
+            Some((&label, rest)) if label.get(self.tree).kind() == Label => {
+                self.element(label);
+                self.text(" ");
+                rest
+            }
+            _ => elements,
+        }
+    }
+
+    /// Whether the node is parsed as a value on its own, without parentheses around it
+    fn atom(&self, id: NodeId) -> bool {
+        let node = self.tree.node(id);
+        match node.kind() {
+            StructLit | Match | EnumLit | List | Tuple | Lambda | Pointer => true,
+            UnaryOp => node
+                .leaves(self.tree)
+                .first()
+                .is_some_and(|op| op.kind() == Mul),
+            _ => false,
+        }
+    }
+
+    /// Prints a destructuring target of a let or a pattern of a match arm
+    fn pattern(&mut self, children: &[TreeElement<NodeId, LeafId>]) {
+        let kinds = children
+            .iter()
+            .map(|e| e.get(self.tree).kind())
+            .collect::<Vec<_>>();
+        match kinds[..] {
+            [OpenParen, ..] => {
+                self.text("(");
+                self.list(&children[1..]);
+                self.text(")");
+            }
+            [_, OpenBrace, ..] => {
+                self.element(children[0]);
+                self.text(" { ");
+                self.list(&children[2..]);
+                self.text(" }");
+            }
+            [_] => self.element(children[0]),
+            _ => {
+                self.element(children[0]);
+                self.text("::");
+                self.element(children[1]);
+                if children.len() > 2 {
+                    self.text("(");
+                    self.list(&children[2..]);
+                    self.text(")");
+                }
+            }
+        }
+    }
+}
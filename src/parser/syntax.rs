@@ -5,6 +5,7 @@ use logos::Logos;
 use crate::{
     error::Error,
     tree::{Tree, TreeBuilder},
+    Span,
 };
 
 #[rustfmt::skip]
@@ -12,15 +13,32 @@ use crate::{
 #[repr(u16)]
 pub enum SyntaxKind {
     #[token("fx")] FxKw,
+    #[token("struct")] StructKw,
+    #[token("enum")] EnumKw,
+    #[token("type")] TypeKw,
+    #[token("match")] MatchKw,
     #[token("return")] ReturnKw,
+    #[token("assert")] AssertKw,
     #[token("box")] BoxKw,
     #[token("loop")] LoopKw,
+    #[token("while")] WhileKw,
+    #[token("for")] ForKw,
+    #[token("break")] BreakKw,
+    #[token("continue")] ContinueKw,
     #[token("else")] ElseKw,
     #[token("if")] IfKw,
     #[token("let")] LetKw,
+    #[token("mut")] MutKw,
+    #[token("const")] ConstKw,
     #[token("file")] FileKw,
     #[token("byte")] ByteKw,
+    #[token("bool")] BoolKw,
+    #[token("float")] FloatKw,
+    #[token("char")] CharKw,
     #[token("in")] InKw,
+    #[token("as")] AsKw,
+    #[token("true")] TrueKw,
+    #[token("false")] FalseKw,
 
     #[token("{")] OpenBrace,
     #[token("}")] CloseBrace,
@@ -31,8 +49,11 @@ pub enum SyntaxKind {
 
     #[token(",")] Comma,
     #[token(":")] Colon,
+    #[token("::")] ColonColon,
     #[token(";")] SemiColon,
     #[token(".")] Dot,
+    #[token("..")] DotDot,
+    #[token("...")] Ellipsis,
     #[token("'")] Quote,
     #[token("?")] Question,
     #[token("=>")] FatArrow,
@@ -62,16 +83,21 @@ pub enum SyntaxKind {
     #[token("==")] Equal,
     #[token("!=")] NotEqual,
     #[token("*")] Mul,
+    #[token("**")] Pow,
     #[token("/")] Div,
     #[token("+")] Add,
     #[token("-")] Sub,
     #[token("<<")] Shl,
     #[token(">>")] Shr,
 
-    #[regex(r#"'(\\(["ntr\\']|x[0-9A-Fa-f]+|[0-7][0-7]?[0-7]?)|[^'])'"#)] Char,
-    #[regex(r#""(\\(["ntr\\']|x[0-9A-Fa-f]+|[0-7][0-7]?[0-7]?)|[^"])*""#)] String,
+    #[regex(r#"'(\\(x[0-9A-Fa-f]*|[0-7][0-7]?[0-7]?|.)|[^'])'"#)] Char,
+    #[regex(r#""(\\(x[0-9A-Fa-f]*|[0-7][0-7]?[0-7]?|.)|[^"])*""#)] String,
     #[regex(r"[a-zA-Z_][a-zA-Z_0-9]*")] Identifier,
-    #[regex(r"\d+")] Number,
+    #[regex(r"\d[0-9a-zA-Z_]*")] Number,
+    // a number with an exponent is a float, even without a fraction
+    #[regex(r"\d[\d_]*(\.\d[\d_]*)?[eE][+-]?\d+", priority = 3)]
+    #[regex(r"\d[\d_]*\.\d[\d_]*")]
+    Float,
 
     #[regex(r"[ \t\n\f]+", logos::skip)] Whitespace,
 
@@ -92,14 +118,35 @@ pub enum SyntaxKind {
     Let,
     ReLet,
     Loop,
+    While,
+    For,
+    Label,
     BinaryOp,
     UnaryOp,
     Call,
+    MethodCall,
+    Index,
+    Access,
     If,
     Statement,
     Value,
     Fx,
     List,
+    Tuple,
+    Struct,
+    StructLit,
+    Field,
+    Enum,
+    Variant,
+    TypeAlias,
+    EnumLit,
+    Match,
+    Arm,
+    Pattern,
+    Lambda,
+    Param,
+    Capture,
+    Const,
 
     Stuffing,
     Error,
@@ -144,15 +191,32 @@ impl Display for SyntaxKind {
             "{}",
             match self {
                 SyntaxKind::FxKw => "'fx'",
+                SyntaxKind::StructKw => "'struct'",
+                SyntaxKind::EnumKw => "'enum'",
+                SyntaxKind::TypeKw => "'type'",
+                SyntaxKind::MatchKw => "'match'",
                 SyntaxKind::ReturnKw => "'return'",
+                SyntaxKind::AssertKw => "'assert'",
                 SyntaxKind::BoxKw => "'box'",
                 SyntaxKind::LoopKw => "'loop'",
+                SyntaxKind::WhileKw => "'while'",
+                SyntaxKind::ForKw => "'for'",
+                SyntaxKind::BreakKw => "'break'",
+                SyntaxKind::ContinueKw => "'continue'",
                 SyntaxKind::ElseKw => "'else'",
                 SyntaxKind::IfKw => "'if'",
                 SyntaxKind::LetKw => "'let'",
+                SyntaxKind::ConstKw => "'const'",
+                SyntaxKind::MutKw => "'mut'",
                 SyntaxKind::FileKw => "'file'",
                 SyntaxKind::ByteKw => "'byte'",
+                SyntaxKind::BoolKw => "'bool'",
+                SyntaxKind::FloatKw => "'float'",
+                SyntaxKind::CharKw => "'char'",
                 SyntaxKind::InKw => "'in'",
+                SyntaxKind::AsKw => "'as'",
+                SyntaxKind::TrueKw => "'true'",
+                SyntaxKind::FalseKw => "'false'",
                 SyntaxKind::OpenBrace => "{",
                 SyntaxKind::CloseBrace => "}",
                 SyntaxKind::OpenBracket => "[",
@@ -161,8 +225,11 @@ impl Display for SyntaxKind {
                 SyntaxKind::CloseParen => ")",
                 SyntaxKind::Comma => ",",
                 SyntaxKind::Colon => ":",
+                SyntaxKind::ColonColon => "::",
                 SyntaxKind::SemiColon => ";",
                 SyntaxKind::Dot => ".",
+                SyntaxKind::DotDot => "..",
+                SyntaxKind::Ellipsis => "...",
                 SyntaxKind::Quote => "'",
                 SyntaxKind::Question => "?",
                 SyntaxKind::FatArrow => "=>",
@@ -190,6 +257,7 @@ impl Display for SyntaxKind {
                 SyntaxKind::Equal => "==",
                 SyntaxKind::NotEqual => "!=",
                 SyntaxKind::Mul => "*",
+                SyntaxKind::Pow => "**",
                 SyntaxKind::Div => "/",
                 SyntaxKind::Add => "+",
                 SyntaxKind::Sub => "-",
@@ -199,6 +267,7 @@ impl Display for SyntaxKind {
                 SyntaxKind::String => "string",
                 SyntaxKind::Identifier => "identifier",
                 SyntaxKind::Number => "number",
+                SyntaxKind::Float => "float",
                 SyntaxKind::Comment => "comment",
                 SyntaxKind::Whitespace => "space",
                 SyntaxKind::Kind => "kind",
@@ -206,13 +275,20 @@ impl Display for SyntaxKind {
                 SyntaxKind::Value => "value",
                 SyntaxKind::Statement => "statement",
                 SyntaxKind::Call => "CALL",
+                SyntaxKind::Index => "INDEX",
+                SyntaxKind::MethodCall => "METHOD CALL",
+                SyntaxKind::Access => "ACCESS",
                 SyntaxKind::List => "[...]",
+                SyntaxKind::Tuple => "(...)",
                 SyntaxKind::BinaryOp => "BINARY OPERATION",
                 SyntaxKind::UnaryOp => "UNARY OPERATION",
                 SyntaxKind::Let => "LET",
                 SyntaxKind::ReLet => "ReLET",
                 SyntaxKind::If => "IF",
                 SyntaxKind::Loop => "LOOP",
+                SyntaxKind::While => "WHILE",
+                SyntaxKind::For => "FOR",
+                SyntaxKind::Label => "LABEL",
                 SyntaxKind::Scope => "SCOPE",
                 SyntaxKind::Eof => "EOF",
                 SyntaxKind::Root => "ROOT",
@@ -221,11 +297,33 @@ impl Display for SyntaxKind {
                 SyntaxKind::Error => "ERROR",
                 SyntaxKind::Stuffing => "STUFFING, yk",
                 SyntaxKind::Fx => "FUNCTION",
+                SyntaxKind::Struct => "STRUCT",
+                SyntaxKind::StructLit => "STRUCT LITERAL",
+                SyntaxKind::Field => "FIELD",
+                SyntaxKind::Enum => "ENUM",
+                SyntaxKind::Variant => "VARIANT",
+                SyntaxKind::TypeAlias => "TYPE ALIAS",
+                SyntaxKind::EnumLit => "ENUM LITERAL",
+                SyntaxKind::Match => "MATCH",
+                SyntaxKind::Arm => "ARM",
+                SyntaxKind::Pattern => "PATTERN",
+                SyntaxKind::Lambda => "LAMBDA",
+                SyntaxKind::Const => "CONSTANT",
+                SyntaxKind::Param => "PARAMETER",
+                SyntaxKind::Capture => "CAPTURE",
             }
         )
     }
 }
 
+/// Token of a source, whitespace and comments are left out between the tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: SyntaxKind,
+    /// Range of bytes of the source taken by the token
+    pub span: Span,
+}
+
 pub type ParseTree = Tree<(), SyntaxKind>;
 pub type ParseTreeBuilder = TreeBuilder<(), SyntaxKind>;
 pub struct ParseResult<'a> {
@@ -264,7 +264,7 @@ impl<L: Debug> Debug for Leaf<L> {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct NodeId(usize);
 
@@ -343,6 +343,22 @@ impl TreeElement<NodeId, LeafId> {
     }
 }
 
+impl<'a, N, L> TreeElement<&'a Node<N>, &'a Leaf<L>> {
+    pub fn span(&self) -> Span {
+        match self {
+            TreeElement::Node(node) => node.span(),
+            TreeElement::Leaf(leaf) => leaf.span(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            TreeElement::Node(node) => node.kind(),
+            TreeElement::Leaf(leaf) => leaf.kind(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TreeBuilder<N, L> {
     leaves: Vec<Leaf<L>>,
@@ -569,7 +585,8 @@ impl<'a, N, L> Iterator for ChildLeafIter<'a, N, L> {
     type Item = TreeElement<NodeId, LeafId>;
     fn next(&mut self) -> Option<Self::Item> {
         let current = &self.tree.nodes[self.node];
-        if self.leaf >= current.leaf_span.end {
+        // children without leaves at the end still have to be visited
+        if self.leaf >= current.leaf_span.end && self.child >= current.children.len() {
             return None;
         }
         let child_id = current.children.get(self.child).copied();
@@ -600,7 +617,8 @@ impl<'a, N, L> Iterator for BuilderChildLeafIter<'a, N, L> {
     type Item = TreeElement<NodeId, LeafId>;
     fn next(&mut self) -> Option<Self::Item> {
         let current = &self.builder.nodes[self.node];
-        if self.leaf >= current.leaf_span.end {
+        // children without leaves at the end still have to be visited
+        if self.leaf >= current.leaf_span.end && self.child >= current.children.len() {
             return None;
         }
         let child_id = current.children.get(self.child).copied();
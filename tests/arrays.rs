@@ -0,0 +1,45 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn sum_array() {
+    let sum = "
+        let a: [byte; 4] = [3, 5, 7, 9];
+        let mut s = 0;
+        let mut i = 0;
+        while i < 4 {
+            s += a[i];
+            i += 1;
+        }
+        s
+    ";
+    assert_eq!(run(sum), 24);
+    assert_eq!(
+        errors("let a = [1, 2];\na[-1] + a[2]"),
+        [
+            "main.snt:2:3: error: Index -1 is out of bounds for an array of length 2",
+            "main.snt:2:11: error: Index 2 is out of bounds for an array of length 2",
+        ]
+    );
+}
+
+#[test]
+fn empty_arrays() {
+    // the annotation gives empty array literals the type of their elements
+    let source = "
+        let a: [byte; 0] = [];
+        let mut b: [[bool; 0]; 2] = [[], []];
+        b = [[], []];
+        let t: ([byte; 0], byte) = ([], 4);
+        t.1
+    ";
+    assert_eq!(run(source), 4);
+    assert_eq!(
+        errors("let c: [byte; 1] = [];\nlet d: [byte; 0] = [true];"),
+        [
+            "main.snt:1:5: error: This value was supposed to be a `[Number; 1]`, but is a `[None; 0]`",
+            "main.snt:2:5: error: This value was supposed to be a `[Number; 0]`, but is a `[Boolean; 1]`",
+        ]
+    );
+}
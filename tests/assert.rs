@@ -0,0 +1,29 @@
+mod common;
+
+use common::{cli, errors};
+use snotty::compiler::ASSERTION_FAILED;
+
+#[test]
+fn asserts() {
+    let source = "
+        fx check(n) {
+            assert n > 2;
+            n
+        }
+        putchar(check(65));
+        check(1)
+    ";
+    // the passing assert prints nothing, the failing one stops the program
+    let output = cli("asserts.snt", source);
+    assert_eq!(output.status.code(), Some(ASSERTION_FAILED.into()));
+    assert_eq!(output.stdout, b"A");
+    assert_eq!(output.stderr, b"assertion failed: n > 2\n");
+
+    assert_eq!(
+        errors("let x = 3;\nassert 2 * 3 == 5;\nassert 5;\nx"),
+        [
+            "main.snt:2:1: error: This assertion is always false",
+            "main.snt:3:8: error: Did not expect to see a `Number` there",
+        ]
+    );
+}
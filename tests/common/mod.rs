@@ -0,0 +1,74 @@
+//! Helpers shared by the tests, not every test uses all of them
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use snotty::{error::CompileError, Program};
+
+/// Program of the source of `main.snt`, which can't include other files
+pub fn program(source: &str) -> Program {
+    Program::with_reader("main.snt", source.to_string(), |path| {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            path.display().to_string(),
+        ))
+    })
+}
+
+/// Runs the source, returning the number its last statement evaluates to
+pub fn run(source: &str) -> i64 {
+    match snotty::compile(&program(source)) {
+        Ok((snotty::Output::Number(n), _)) => n,
+        Ok((output, _)) => panic!("expected a number, found {output}"),
+        Err(err) => panic!("{err:#}"),
+    }
+}
+
+/// Runs the source, returning the float its last statement evaluates to
+pub fn run_float(source: &str) -> f64 {
+    match snotty::compile(&program(source)) {
+        Ok((snotty::Output::Float(n), _)) => n,
+        Ok((output, _)) => panic!("expected a float, found {output}"),
+        Err(err) => panic!("{err:#}"),
+    }
+}
+
+/// Errors keeping the source from compiling, each as a single `path:line:column: error: ...` line
+pub fn errors(source: &str) -> Vec<String> {
+    match snotty::compile(&program(source)) {
+        Err(CompileError::Analysis(errors)) => errors.iter().map(|e| format!("{e:#}")).collect(),
+        Err(err) => panic!("{err:#}"),
+        Ok((output, _)) => panic!("expected errors, the program returned {output}"),
+    }
+}
+
+/// Runs the source with the command line compiler. The source is written to a file of the given
+/// name, which must be unique to the test
+pub fn cli(name: &str, source: &str) -> Output {
+    cli_with_input(name, source, "")
+}
+
+/// Runs the source with the command line compiler like `cli`, feeding it the input on stdin
+pub fn cli_with_input(name: &str, source: &str, input: &str) -> Output {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, source).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_snotty"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
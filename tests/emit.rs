@@ -0,0 +1,93 @@
+mod common;
+
+use common::program;
+use snotty::parser::{
+    lex,
+    syntax::{SyntaxKind, Token},
+    Parser,
+};
+
+#[test]
+fn ir() {
+    let program = program("fx triple(n) n * 3; triple(14)");
+    let (ir, warnings) = snotty::emit_ir(&program).unwrap();
+    assert!(warnings.is_empty());
+    // the program comes first, followed by its functions
+    let functions = ir.split("\n\nfunction ").collect::<Vec<_>>();
+    assert_eq!(functions.len(), 2);
+    assert!(functions[0].starts_with("function u0:0() -> i64"));
+    assert!(functions[0].contains("iconst.i64 14"));
+    assert!(functions[0].contains("call_indirect"));
+    assert!(functions[1].starts_with("u0:0(i64) -> i64"));
+    assert!(functions[1].contains("imul"));
+}
+
+/// Parse tree of the source without the spans, which printing moves
+fn shape(source: &str) -> String {
+    let parsed = Parser::new(source).parse();
+    assert!(parsed.errors.is_empty(), "{source}");
+    format!("{:?}", parsed.parse)
+        .lines()
+        .map(|line| line.split('@').next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn source_round_trip() {
+    let samples = [
+        "let mut i = 0; while i < 10 { i += 1; } loop (let mut j = 0; j < 3; j += 1) {} i",
+        "fx add(a, b = 2) -> byte a + b\nfx pos(n) { if n < 0 { return 0 } n }\nadd(pos(1), b: 3)",
+        "struct Point { x: byte, y: byte }\nlet p: Point = Point { x: 1, y: 2 };\np.x * (p.y + 1)",
+        "enum Shape { Dot, Rect(byte, byte) }\nlet c = Shape::Rect(2, 5);\nmatch c { Shape::Rect(w, h) => w + h, _ => 0 }",
+        "const TEN: byte = 10;\nlet add = |x| x + TEN;\nlet (a, b) = (add(1), [1, 2][0]);\na > b ? -a : !(b == 1)",
+        "let p = {5};\nlet q = {{1 + 2}};\n*p = *p + **q;\nfor :o k in 0..5 { if k == 2 { continue :o; } }\n'a' + \"b\"",
+    ];
+    for source in samples {
+        let program = program(source);
+        let printed = snotty::emit_source(&program).unwrap();
+        assert_eq!(shape(&printed), shape(source), "{printed}");
+    }
+}
+
+#[test]
+fn tokens() {
+    use SyntaxKind::*;
+
+    let source = "let x = 0x1F; \\\\ note\nfx f(a) a * 2.5";
+    let tokens = lex(source).unwrap();
+    let expected = [
+        (LetKw, 0..3),
+        (Identifier, 4..5),
+        (Assign, 6..7),
+        (Number, 8..12),
+        (SemiColon, 12..13),
+        (FxKw, 22..24),
+        (Identifier, 25..26),
+        (OpenParen, 26..27),
+        (Identifier, 27..28),
+        (CloseParen, 28..29),
+        (Identifier, 30..31),
+        (Mul, 32..33),
+        (Float, 34..37),
+    ];
+    let expected = expected.map(|(kind, span)| Token { kind, span }).to_vec();
+    assert_eq!(tokens, expected);
+
+    let errors = lex("let $ = 1 # 2").unwrap_err();
+    let errors = errors.iter().map(|e| format!("{e:#}")).collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "<program>:1:5: error: I don't recognize this syntax",
+            "<program>:1:11: error: I don't recognize this syntax",
+        ]
+    );
+
+    // the command line tokens go through the same lexer
+    let program = program("let x = 1;");
+    assert_eq!(
+        snotty::emit_tokens(&program).unwrap(),
+        "'let' 0..3 \"let\"\nidentifier 4..5 \"x\"\n= 6..7 \"=\"\nnumber 8..9 \"1\"\n; 9..10 \";\"\n"
+    );
+}
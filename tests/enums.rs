@@ -0,0 +1,66 @@
+mod common;
+
+use common::{errors, program, run};
+use snotty::Output;
+
+#[test]
+fn matches() {
+    let source = "
+        enum Shape { Dot, Square(byte), Rect(byte, byte) }
+        let area = |s: Shape| match s { Shape::Dot => 0, Shape::Square(a) => a * a, Shape::Rect(w, h) => w * h };
+        let n = 3;
+        let a = match n { 1 => 10, 3 => 30, x => x };
+        let b = match 7 { 1 => 10, _ => 5 };
+        let c = Shape::Rect(2, 5);
+        let d = match c { Shape::Rect(w, h) => w + h, _ => 0 };
+        a + b + d + area(Shape::Square(4)) * 100 + area(c) * 1000 + area(Shape::Dot)
+    ";
+    assert_eq!(run(source), 11642);
+}
+
+#[test]
+fn invalid_matches() {
+    let source = "
+enum Shape { Dot, Square(byte) }
+let c = Shape::Dot;
+let a = match c { Shape::Dot => 1 };
+let b = match c { Shape::Square(x, y) => 1, Shape::Dot => 2, Shape::Blob => 3 };
+let d = match 5 { 1 => 2 };
+let e = match c { 1 => 2, _ => 3 };
+a";
+    assert_eq!(
+        errors(source),
+        [
+            "main.snt:4:9: error: This match does not handle every possible value",
+            "main.snt:5:9: error: This match does not handle every possible value",
+            "main.snt:5:19: error: The function expects 1 arguments but 2 values were passed in",
+            "main.snt:5:69: error: This thing is not defined",
+            "main.snt:6:9: error: This match does not handle every possible value",
+            "main.snt:7:19: error: This value was supposed to be a `Shape`, but is a `Number`",
+        ]
+    );
+}
+
+#[test]
+fn redundant_arms() {
+    assert_eq!(
+        errors("let n = 3;\nlet a = match n { 1 => 1, 0x1 => 2, _ => 3 };\na"),
+        ["main.snt:2:27: error: This arm is never taken, one before it matches the same value"]
+    );
+    // arms after a catch-all are warned about, not rejected
+    let program = program(
+        "let n = 3;\nlet a = match n { 1 => 10, _ => 5, 3 => 30 };\nlet b = match n { x => x, 2 => 1 };\na + b",
+    );
+    let (output, warnings) = snotty::compile(&program).unwrap();
+    assert_eq!(output, Output::Number(8));
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|w| format!("{w:#}"))
+            .collect::<Vec<_>>(),
+        [
+            "main.snt:2:36: warning: This arm is never taken, one before it matches every value",
+            "main.snt:3:27: warning: This arm is never taken, one before it matches every value",
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::io;
+
+use common::{cli, errors, program};
+use snotty::{error::CompileError, Output, Program};
+
+#[test]
+fn compile_error_variants() {
+    let Err(CompileError::Io(err)) = Program::load("tests/missing.snt") else {
+        panic!("a missing file should fail to load");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    assert_eq!(
+        errors("let a = 1;\nlet b: bool = a;"),
+        ["main.snt:2:5: error: This value was supposed to be a `Boolean`, but is a `Number`"]
+    );
+}
+
+#[test]
+fn syntax_errors() {
+    let source = "
+let a = (1;
+{
+    let b = [1 2];
+    let c = 3;
+}
+let = 4;
+let d = 5;
+d";
+    assert_eq!(
+        errors(source),
+        [
+            "main.snt:2:11: error: Expected to see a `)` there instead",
+            "main.snt:4:16: error: Expected to see a `]` there instead",
+            "main.snt:7:5: error: Expected to see a `identifier` there instead",
+        ]
+    );
+    // unclosed parameters end at the brace of the body or with their line
+    assert_eq!(
+        errors("fx f(a { a }\nf(1)"),
+        ["main.snt:1:8: error: Expected to see a `)` there instead"]
+    );
+    assert_eq!(
+        errors("fx f(a b\n    a\nf(1)"),
+        ["main.snt:1:8: error: Expected to see a `)` there instead"]
+    );
+}
+
+#[test]
+fn multi_line_spans() {
+    let source = "
+let a = 1;
+let b = a > 0 ? 1 : (
+    true
+);
+fx f(x) x
+let c = f(
+    1,
+    2
+) + a; let d = c +
+    true;
+d";
+    assert_eq!(
+        errors(source),
+        [
+            "main.snt:3:21: error: Did not expect to see a `Boolean` there",
+            "main.snt:7:9: error: The function expects 1 arguments but 2 values were passed in",
+            "main.snt:10:18: error: Operation `+` is not doable here",
+        ]
+    );
+    // the span is underlined up to its end on the last line, not its start column
+    let program = program(source);
+    let Err(CompileError::Analysis(errors)) = snotty::compile(&program) else {
+        panic!("the source should not compile");
+    };
+    let rendered = format!("{}", errors[0]);
+    let underlines = rendered
+        .split('\x1b')
+        .map(|s| s.split_once('m').map_or(s, |(_, s)| s))
+        .collect::<String>();
+    assert!(underlines.contains("  5 | );\n    | ^\n"), "{underlines}");
+}
+
+#[test]
+fn warnings() {
+    let source = "
+fx f(a) {
+    let unused = a + 1;
+    return a * 2;
+    a
+}
+if 1 < 2 { 5 } else { 6 }
+f(4)";
+    let expected = [
+        "main.snt:3:9: warning: `unused` is never used",
+        "main.snt:5:5: warning: This code is never reached",
+        "main.snt:7:4: warning: This condition is always true",
+    ];
+    let mut program = program(source);
+    let (output, warnings) = snotty::compile(&program).unwrap();
+    assert_eq!(output, Output::Number(8));
+    let warnings = warnings
+        .iter()
+        .map(|w| format!("{w:#}"))
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, expected);
+
+    program.set_deny_warnings(true);
+    let Err(CompileError::Analysis(errors)) = snotty::compile(&program) else {
+        panic!("denied warnings should keep the program from compiling");
+    };
+    let errors = errors.iter().map(|e| format!("{e:#}")).collect::<Vec<_>>();
+    assert_eq!(errors, expected.map(|w| w.replace("warning", "error")));
+
+    // the warnings printed by the compiler are separated like its errors
+    let output = cli("warnings.snt", "let a = 1;\nlet b = 2;\n3");
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let stderr = stderr
+        .split('\x1b')
+        .map(|s| s.split_once('m').map_or(s, |(_, s)| s))
+        .collect::<String>();
+    let warnings = stderr.strip_suffix("\n\n").unwrap().split("\n\n");
+    assert!(
+        warnings
+            .map(|w| w.starts_with("warning: "))
+            .eq([true, true]),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn misplaced_arguments() {
+    // only the misplaced argument is reported, not the count or the parameters it leaves out
+    assert_eq!(
+        errors("fx f(a, b) a * 10 + b\nf(1, a: 2);\nf(b: 2, 3)"),
+        [
+            "main.snt:2:6: error: This argument was already given",
+            "main.snt:3:9: error: This argument can not follow arguments given by name",
+        ]
+    );
+}
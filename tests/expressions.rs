@@ -0,0 +1,171 @@
+mod common;
+
+use common::{cli, errors, run, run_float};
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(
+        errors("let a = 5 / (2 - 2);\nlet mut x = 7;\nx %= (0);\nx + a"),
+        [
+            "main.snt:1:11: error: This divides by zero",
+            "main.snt:3:3: error: This divides by zero",
+        ]
+    );
+    // the divisors fold to numbers other than zero
+    assert_eq!(run("let mut x = 7;\nx /= 4 - 3;\nx / (3 - 1)"), 3);
+    // divisors only known at run time give a quotient of 0 and leave the dividend as remainder
+    let source = "
+        fx div(a, b) a / b
+        fx rem(a, b) a % b
+        let mut x = 7;
+        let mut z = 0;
+        x /= z;
+        let mut y = 0 - 9;
+        y %= z;
+        (div(7, 0), rem(7, 0), rem(0 - 7, 0), x, y)
+    ";
+    assert_eq!(run(&format!("{source}.0")), 0);
+    assert_eq!(run(&format!("{source}.1")), 7);
+    assert_eq!(run(&format!("{source}.2")), -7);
+    assert_eq!(run(&format!("{source}.3")), 0);
+    assert_eq!(run(&format!("{source}.4")), -9);
+}
+
+#[test]
+fn constant_overflow() {
+    assert_eq!(
+        errors("let a = 2147483647 * 2147483647 * 4;\nlet b = 2 ** 63;\na + b"),
+        [
+            "main.snt:1:33: error: This overflows a byte",
+            "main.snt:2:11: error: This overflows a byte",
+        ]
+    );
+    // constants too big for a literal still fit in a byte
+    assert_eq!(
+        run("let a = 2147483647 * 2147483647;\n1 - 2 - 3 + (2 ** 62 - 1 + 2 ** 62) - a"),
+        9223372036854775807 - 4 - 2147483647 * 2147483647
+    );
+}
+
+#[test]
+fn powers() {
+    // powers are right associative and bind tighter than negation
+    assert_eq!(run("2**3**2 == 512"), 1);
+    assert_eq!(run("-2**2 == -4"), 1);
+    assert_eq!(run("-2**2**2"), -16);
+    assert_eq!(run("fx n(a) a;\nn(2)**n(3)**n(2) - n(2)**2"), 508);
+}
+
+#[test]
+fn shifts() {
+    // amounts of the width of a number or more, negative ones included, shift every bit out
+    let source = "
+        fx shl(a, b) a << b
+        fx shr(a, b) a >> b
+        let x = 0 - 8;
+        (1 << 64, shl(1, 64), shl(1, 0 - 1), x >> 64, shr(x, 64), shr(8, 64), shr(x, 0 - 1))
+    ";
+    for (i, expected) in [0, 0, 0, -1, -1, 0, -1].into_iter().enumerate() {
+        assert_eq!(run(&format!("{source}.{i}")), expected);
+    }
+    assert_eq!(run("fx shl(a, b) a << b\nshl(1, 63)"), i64::MIN);
+    assert_eq!(run("let x = 0 - 8;\n(x >> 1) * (1 << 40)"), -4 << 40);
+}
+
+#[test]
+fn ternaries() {
+    let source = "
+        fx twice(n) n * 2
+        let a = 1;
+        let b = 2;
+        let x = a < b ? a > 0 ? 10 : 20 : 30;
+        x + twice(a == b ? 100 : 3)
+    ";
+    assert_eq!(run(source), 16);
+    assert_eq!(
+        errors("let a = 1;\na > 0 ? 1 : true"),
+        ["main.snt:2:13: error: Did not expect to see a `Boolean` there"]
+    );
+}
+
+#[test]
+fn if_chains() {
+    let source = "
+        fx grade(n) if n > 90 { return 1; } else if n > 50 { return 2; } else if n > 10 {
+            return 3;
+        } else {
+            return 4;
+        }
+        grade(95) * 1000 + grade(60) * 100 + grade(20) * 10 + grade(5)
+    ";
+    assert_eq!(run(source), 1234);
+    assert_eq!(
+        errors("let x = 1;\nif x > 0 { 1 } else if { 2 } else 3"),
+        ["main.snt:2:30: error: Expected to see a `value` there instead"]
+    );
+}
+
+#[test]
+fn pointers() {
+    let source = "
+        let p = {5};
+        let q = {{1 + 2}};
+        *p = *p + **q;
+        **q = 10;
+        let r = *q;
+        *r + *p
+    ";
+    assert_eq!(run(source), 18);
+    // indexes and fields are taken before dereferencing
+    assert_eq!(
+        run("let a = [{1}, {2}];\nlet t = (0, {{5}});\n*a[1] * 10 + **t.1"),
+        25
+    );
+    assert_eq!(
+        errors("let a = 5;\n*a"),
+        ["main.snt:2:1: error: Operation `*` is not doable here"]
+    );
+}
+
+#[test]
+fn string_compound_assignment() {
+    let source = r#"
+        let mut a = ["x"];
+        a[0] += "y";
+        let mut t = ("a", 1);
+        t.0 += "b";
+        let p = {"q"};
+        *p += "r";
+        puts(a[0]);
+        puts(t.0);
+        puts(*p);
+        0
+    "#;
+    let output = cli("string_compound_assignment.snt", source);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"xy\nab\nqr\n0\n");
+}
+
+#[test]
+fn field_compound_assignment() {
+    let source = "
+        struct P { x: float, y: byte }
+        struct L { a: P, b: P }
+        let mut l = L { a: P { x: 1.5, y: 2 }, b: P { x: 0.5, y: 3 } };
+        l.a.x -= 0.25;
+        l.b.y *= 5;
+        l.a.y <<= 2;
+        (l.a.x * 4.0) as byte * 100 + l.b.y + l.a.y
+    ";
+    assert_eq!(run(source), 523);
+}
+
+#[test]
+fn float_compound_assignment() {
+    assert_eq!(run_float("let mut a = 1.5;\na += 1.0;\na"), 2.5);
+    assert_eq!(run_float("let mut a = 1.5;\na -= 2.0;\na"), -0.5);
+    assert_eq!(run_float("let mut a = 1.5;\na *= 2.0;\na"), 3.0);
+    assert_eq!(run_float("let mut a = 1.5;\na /= 0.5;\na"), 3.0);
+    assert_eq!(run_float("let mut a = [1.5];\na[0] *= 3.0;\na[0]"), 4.5);
+    assert_eq!(run_float("let p = {1.5};\n*p -= 0.25;\n*p"), 1.25);
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, io, path::Path};
+
+use snotty::{error::CompileError, Output, Program};
+
+/// Program of `main.snt`, including the given files instead of reading them from the file system
+fn program(source: &str, files: &[(&str, &str)]) -> Program {
+    let files = files.iter().copied().collect::<HashMap<_, _>>();
+    Program::with_reader("main.snt", source.to_string(), |path: &Path| {
+        match files.get(path.to_str().unwrap()) {
+            Some(source) => Ok(source.to_string()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    })
+}
+
+fn errors(program: &Program) -> Vec<String> {
+    match snotty::compile(program) {
+        Err(CompileError::Analysis(errors)) => errors.iter().map(|e| format!("{e:#}")).collect(),
+        Err(err) => panic!("{err:#}"),
+        Ok((output, _)) => panic!("expected errors, the program returned {output}"),
+    }
+}
+
+#[test]
+fn included_files() {
+    let lib = "fx twice(n) n * 2\nconst TEN = 10;";
+    let main = program("file \"lib.snt\"\ntwice(TEN + 1)", &[("lib.snt", lib)]);
+    assert_eq!(snotty::compile(&main).unwrap().0, Output::Number(22));
+
+    let cyclic = program(
+        "file \"a.snt\"\n0",
+        &[("a.snt", "file \"b.snt\""), ("b.snt", "file \"a.snt\"")],
+    );
+    assert_eq!(
+        errors(&cyclic),
+        ["b.snt:1:6: error: This file ends up including the file including it: a.snt -> b.snt -> a.snt"]
+    );
+
+    let duplicate = program(
+        "file \"lib.snt\"\nfx twice(n) n + n\ntwice(1)",
+        &[("lib.snt", lib)],
+    );
+    assert_eq!(
+        errors(&duplicate),
+        [
+            "main.snt:2:4: error: This thing is already defined",
+            "lib.snt:1:4: help: It was first defined here",
+        ]
+    );
+
+    let missing = program("file \"gone.snt\"\n0", &[]);
+    assert_eq!(
+        errors(&missing),
+        ["main.snt:1:6: error: This file could not be read: entity not found"]
+    );
+}
+
+#[test]
+fn spans_after_includes() {
+    // the lines of an included file don't shift the ones of the file including it
+    let lib = "fx twice(n) n * 2\n\nfx bad(n) n +\n    true";
+    let main = program(
+        "file \"lib.snt\"\nlet a = 1;\nlet b = a > 0 ? twice(\n    1\n) : (\n    true\n);\nb",
+        &[("lib.snt", lib)],
+    );
+    assert_eq!(
+        errors(&main),
+        [
+            "lib.snt:3:13: error: Operation `+` is not doable here",
+            "main.snt:5:5: error: Did not expect to see a `Boolean` there",
+        ]
+    );
+}
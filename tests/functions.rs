@@ -0,0 +1,57 @@
+mod common;
+
+use common::{cli_with_input, errors, run};
+
+#[test]
+fn mutual_recursion() {
+    let source = "
+        fx is_even(n) n == 0 ? 1 : is_odd(n - 1)
+        fx is_odd(n) n == 0 ? 0 : is_even(n - 1)
+        is_even(10) * 10 + is_odd(7)
+    ";
+    assert_eq!(run(source), 11);
+}
+
+#[test]
+fn outer_variables() {
+    let source = "
+let k = 10;
+fx f(a) a + k
+fx g(n) {
+    let m = n * 2;
+    fx h(x) x + m
+    h(1)
+}
+f(1) + g(3)";
+    assert_eq!(
+        errors(source),
+        [
+            "main.snt:3:13: error: `k` is defined outside of this function, which can't use it",
+            "main.snt:2:5: help: It is defined here, a lambda could capture it",
+            "main.snt:6:17: error: `m` is defined outside of this function, which can't use it",
+            "main.snt:5:9: help: It is defined here, a lambda could capture it",
+        ]
+    );
+    // lambdas capture them, and constants can be used anywhere
+    let source = "
+        const TEN = 10;
+        fx twice(n) {
+            let add = |x| x + n;
+            add(add(TEN))
+        }
+        twice(4)
+    ";
+    assert_eq!(run(source), 18);
+}
+
+#[test]
+fn input() {
+    let output = cli_with_input("input.snt", "getnum() * 2", "21\n");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"42\n");
+    // `in` is only a keyword of `for` loops, not a way to read the input
+    assert_eq!(
+        errors("let a = in;\nfor i in 0..a {}"),
+        ["main.snt:1:9: error: 'in' can not be used here"]
+    );
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn captures() {
+    let source = "
+        let a = 3;
+        let b = 10;
+        let add = |x| x + a;
+        let mul = |x: byte, y: byte| x * y + b;
+        let nested = |x| (|y| y + a + x)(b);
+        add(4) + mul(2, 3) + nested(1) + 5.add()
+    ";
+    assert_eq!(run(source), 45);
+    // a lambda captures the variables of the function it is in
+    let source = "
+        fx twice(n) {
+            let add = |x| x + n;
+            add(add(0))
+        }
+        twice(4)
+    ";
+    assert_eq!(run(source), 8);
+}
+
+#[test]
+fn invalid_lambdas() {
+    assert_eq!(
+        errors("let f = |x: bool| x;\nf(1);\nf(true, 2);\nlet g = |y: foo| y;"),
+        [
+            "main.snt:2:3: error: This value was supposed to be a `Boolean`, but is a `Number`",
+            "main.snt:3:1: error: The function expects 1 arguments but 2 values were passed in",
+            "main.snt:4:13: error: The type of this thing is unknown",
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{cli, errors, run};
+
+#[test]
+fn digit_separators() {
+    assert_eq!(run("1_000"), run("1000"));
+    assert_eq!(run("0xFF_FF + 0b1111_0000"), 0xFFFF + 0b1111_0000);
+    assert_eq!(
+        errors("let a = 1__0;\nlet b = 10_;\n_10"),
+        [
+            "main.snt:1:10: error: An underscore can only be put between two digits",
+            "main.snt:2:11: error: An underscore can only be put between two digits",
+            "main.snt:3:1: error: This thing is not defined",
+        ]
+    );
+}
+
+#[test]
+fn prefixed_numbers() {
+    assert_eq!(run("0xFF"), 255);
+    assert_eq!(run("0b1010"), 10);
+    assert_eq!(run("0o17"), 15);
+    assert_eq!(run("0XA + 0B11 + 0O7"), 20);
+    assert_eq!(
+        errors("let a = 0b102;\nlet b = 0o8;\n0xG"),
+        [
+            "main.snt:1:13: error: This is not a digit of a base 2 number",
+            "main.snt:2:11: error: This is not a digit of a base 8 number",
+            "main.snt:3:3: error: This is not a digit of a base 16 number",
+        ]
+    );
+}
+
+#[test]
+fn chars() {
+    let source = r"
+        putchar('A');
+        putchar('\n');
+        'a' - 'A' + '\t' - '\\' + ('\'' < 'b' ? 1 : 0)
+    ";
+    let output = cli("chars.snt", source);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A\n-50\n");
+}
+
+#[test]
+fn empty_structs() {
+    // an identifier before an empty block is not a struct literal in statement conditions
+    let source = "
+        struct E {}
+        let e: E = E {};
+        let mut n = 0;
+        let f = false;
+        if f {} else n += 1;
+        while f {}
+        n
+    ";
+    assert_eq!(run(source), 1);
+    assert_eq!(
+        errors("struct P { x: byte }\nlet p: P = P {};"),
+        ["main.snt:2:12: error: The field `x` is missing here"]
+    );
+}
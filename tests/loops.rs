@@ -0,0 +1,57 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn while_loops() {
+    // empty body
+    assert_eq!(run("let mut i = 0; while i > 0 {} i"), 0);
+    // nested
+    let nested = "
+        let mut i = 0;
+        let mut s = 0;
+        while i < 10 {
+            let mut j = 0;
+            while j < i {
+                s += 1;
+                j += 1;
+            }
+            i += 1;
+        }
+        s
+    ";
+    assert_eq!(run(nested), 45);
+    // call as condition
+    let call = "
+        let mut i = 0;
+        fx below(n, max) n < max
+        while below(i, 7) {
+            i += 1;
+        }
+        i
+    ";
+    assert_eq!(run(call), 7);
+}
+
+#[test]
+fn for_loops() {
+    let source = "
+        let mut s = 0;
+        for i in 1..5 s += i;
+        for :o j in 0..10 {
+            if j == 3 { break :o; }
+            s += 100;
+        }
+        s
+    ";
+    assert_eq!(run(source), 310);
+    // the range is left out when it is empty
+    assert_eq!(run("let mut s = 1;\nfor i in 5..2 s = 0;\ns"), 1);
+    assert_eq!(
+        errors("for i in 0..true {}\nbreak;"),
+        [
+            "main.snt:1:13: error: This value was supposed to be a `Number`, but is a `Boolean`",
+            "main.snt:2:1: error: 'break' can not be used here",
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn mutable_bindings() {
+    assert_eq!(run("let mut a = 1;\na = 2;\na += 3;\na"), 5);
+    assert_eq!(
+        errors("let a = 1;\na = 2;\na += 3;\na"),
+        [
+            "main.snt:2:1: error: `a` can't be assigned, it was declared without `mut`",
+            "main.snt:1:5: help: Declaring it with `let mut` would make it assignable",
+            "main.snt:3:1: error: `a` can't be assigned, it was declared without `mut`",
+            "main.snt:1:5: help: Declaring it with `let mut` would make it assignable",
+        ]
+    );
+    // shadowing with a mutable binding
+    assert_eq!(run("let a = 1;\nlet mut a = a + 1;\na = a * 10;\na"), 20);
+    // the mutable binding gets a copy, the immutable one is left as is
+    assert_eq!(
+        run("let a = [1, 2];\nlet mut b = a;\nb[0] = 5;\na[0] * 10 + b[0]"),
+        15
+    );
+}
+
+#[test]
+fn mutable_places() {
+    assert_eq!(
+        run("let mut a = [[1, 2], [3, 4]];\na[0][1] = 5;\na[1][0] += 1;\na[0][1] * 10 + a[1][0]"),
+        54
+    );
+    assert_eq!(
+        errors("let a = [1, 2];\na[0] = 5;\nlet t = (1, 2);\nt.1 += 5;"),
+        [
+            "main.snt:2:1: error: `a` can't be assigned, it was declared without `mut`",
+            "main.snt:1:5: help: Declaring it with `let mut` would make it assignable",
+            "main.snt:4:1: error: `t` can't be assigned, it was declared without `mut`",
+            "main.snt:3:5: help: Declaring it with `let mut` would make it assignable",
+        ]
+    );
+    assert_eq!(
+        errors("struct P { x: byte }\nlet p = P { x: 1 };\np.x = 2;"),
+        [
+            "main.snt:3:1: error: `p` can't be assigned, it was declared without `mut`",
+            "main.snt:2:5: help: Declaring it with `let mut` would make it assignable",
+        ]
+    );
+    assert_eq!(
+        errors("let n = [[1], [2]];\nn[1][0] = 3;"),
+        [
+            "main.snt:2:1: error: `n` can't be assigned, it was declared without `mut`",
+            "main.snt:1:5: help: Declaring it with `let mut` would make it assignable",
+        ]
+    );
+    // the pointee of an immutable pointer is still assignable
+    assert_eq!(run("let p = {1};\n*p += 3;\n*p"), 4);
+}
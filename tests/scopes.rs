@@ -0,0 +1,47 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn block_scopes() {
+    let source = "
+        let mut x = 1;
+        let mut inner = 0;
+        {
+            let x = 10;
+            inner = x;
+        }
+        {
+            x = x + 2;
+        }
+        inner * 10 + x
+    ";
+    assert_eq!(run(source), 103);
+    assert_eq!(
+        errors("{\n    let inner = 2;\n}\ninner"),
+        ["main.snt:4:1: error: This thing is not defined"]
+    );
+}
+
+#[test]
+fn use_before_declaration() {
+    assert_eq!(
+        errors("{\n    let a = x + 1;\n    let x = 2;\n}"),
+        [
+            "main.snt:2:13: error: `x` is used before it is declared",
+            "main.snt:3:9: help: It is declared here, after its use",
+        ]
+    );
+    // a name declared in an outer scope is used until the inner declaration
+    let shadowed = "
+        let x = 1;
+        let mut r = 0;
+        {
+            let a = x + 10;
+            let x = 5;
+            r = a + x;
+        }
+        r
+    ";
+    assert_eq!(run(shadowed), 16);
+}
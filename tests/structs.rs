@@ -0,0 +1,54 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn fields() {
+    let source = "
+        struct Point { x: byte, y: byte }
+        struct Line { a: Point, b: Point }
+        let p = Point { x: 1, y: 2 };
+        let l = Line { b: Point { y: 4, x: 3 }, a: p };
+        let Point { x, y } = l.b;
+        l.a.x + l.b.y * 10 + x * 100 + y * 1000
+    ";
+    assert_eq!(run(source), 4341);
+    assert_eq!(
+        errors("struct Q { a: byte, b: byte }\nlet a = Q { a: 1, c: 2 };\nlet b = Q { a: true, b: 1 };\na.d"),
+        [
+            "main.snt:2:9: error: The field `b` is missing here",
+            "main.snt:2:19: error: This thing is not defined",
+            "main.snt:3:16: error: This value was supposed to be a `Number`, but is a `Boolean`",
+            "main.snt:4:3: error: This thing is not defined",
+        ]
+    );
+}
+
+#[test]
+fn methods() {
+    // the receiver is passed as the first argument
+    let source = "
+        fx add(a, b) a + b
+        fx double(a) a * 2
+        let x = 5;
+        x.double().add(3).add(x)
+    ";
+    assert_eq!(run(source), 18);
+    assert_eq!(
+        errors("fx double(a) a * 2\nlet x = 5;\nx.double(1)"),
+        ["main.snt:3:1: error: The function expects 1 arguments but 2 values were passed in"]
+    );
+    // parameters with a kind take receivers that are not numbers
+    let source = "
+        struct Point { x: byte, y: byte }
+        fx dist(p: Point) p.x + p.y
+        fx scale(p: Point, k = 2) -> byte p.dist() * k
+        let p = Point { x: 1, y: 2 };
+        p.dist() * 10 + p.scale(k: 3)
+    ";
+    assert_eq!(run(source), 39);
+    assert_eq!(
+        errors("struct Point { x: byte }\nfx dist(p: Point) p.x\nlet x = 5;\nx.dist()"),
+        ["main.snt:4:1: error: This value was supposed to be a `Point`, but is a `Number`"]
+    );
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{errors, run};
+
+#[test]
+fn annotations() {
+    let matching = "
+        let a: byte = 5;
+        let p: *byte = {a};
+        fx f(n) -> byte n * 2
+        f(*p) + a
+    ";
+    assert_eq!(run(matching), 15);
+    assert_eq!(
+        errors("let a: bool = 5;"),
+        ["main.snt:1:5: error: This value was supposed to be a `Boolean`, but is a `Number`"]
+    );
+    assert_eq!(
+        errors("let a: *byte = {1};\nlet b: *bool = a;\nfx f(n) -> bool n * 2"),
+        [
+            "main.snt:2:5: error: This value was supposed to be a `*Boolean`, but is a `*Number`",
+            "main.snt:3:17: error: This value was supposed to be a `Boolean`, but is a `Number`",
+        ]
+    );
+}
+
+#[test]
+fn booleans() {
+    // comparisons and logical operations on them are booleans
+    let source = "
+        let x = 5;
+        let b = (x < 3) | (x == 5);
+        let c: bool = b & x > 9;
+        if x > 2 & b { c ? 2 : 1 } else { 3 }
+    ";
+    assert_eq!(run(source), 1);
+    assert_eq!(
+        errors("let x = 5;\nlet b: bool = x + 1;\nlet c: byte = x < 1;"),
+        [
+            "main.snt:2:5: error: This value was supposed to be a `Boolean`, but is a `Number`",
+            "main.snt:3:5: error: This value was supposed to be a `Number`, but is a `Boolean`",
+        ]
+    );
+}
+
+#[test]
+fn inference_cycles() {
+    // the cycles come from a use before the declaration, which is the only error
+    assert_eq!(
+        errors("let x = x;\nx"),
+        [
+            "main.snt:1:9: error: `x` is used before it is declared",
+            "main.snt:1:5: help: It is declared here, after its use",
+        ]
+    );
+    assert_eq!(
+        errors("let a = b;\nlet b = a;\na"),
+        [
+            "main.snt:1:9: error: `b` is used before it is declared",
+            "main.snt:2:5: help: It is declared here, after its use",
+        ]
+    );
+    // the types are inferred through the values they depend on
+    let source = "
+        let q = 3;
+        let p = {q};
+        let t = (*p, p);
+        let l = |x| x;
+        *t.1 + l(t.0)
+    ";
+    assert_eq!(run(source), 6);
+    assert_eq!(
+        errors("let l = |x| x;\nlet c = z(1);\n0"),
+        ["main.snt:2:9: error: This thing is not defined"]
+    );
+}
+
+#[test]
+fn alias_cycles() {
+    assert_eq!(
+        errors("type A = B;\ntype B = A;\nlet x: A = 1;\n0"),
+        ["main.snt:1:6: error: This type alias ends up referring to itself: A -> B -> A"]
+    );
+    assert_eq!(
+        errors("type A = [B; 2];\ntype B = (C, byte);\ntype C = *A;\ntype D = D;\n0"),
+        [
+            "main.snt:1:6: error: This type alias ends up referring to itself: A -> B -> C -> A",
+            "main.snt:4:6: error: This type alias ends up referring to itself: D -> D",
+        ]
+    );
+    assert_eq!(
+        run("type B = byte;\ntype A = (B, B);\nlet x: A = (1, 2);\nx.1"),
+        2
+    );
+}